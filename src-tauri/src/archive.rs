@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::Config;
+use crate::db::{self, Database};
+use crate::flac;
+
+/// Compresses a finished recording into the audio archive and returns its path.
+pub fn archive_recording(wav_path: &Path) -> Result<PathBuf> {
+    let dir = Config::get_audio_dir()?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create audio directory at {:?}", dir))?;
+    let stem = wav_path.file_stem().context("Recording has no file name")?;
+    let dest = dir.join(stem).with_extension("flac");
+    flac::encode_wav_file(wav_path, &dest)?;
+    Ok(dest)
+}
+
+/// Decodes an archived FLAC recording back to a 16-bit WAV whisper can read.
+pub fn restore_to_wav(flac_path: &Path, wav_path: &Path) -> Result<()> {
+    let mut reader = claxon::FlacReader::open(flac_path)
+        .with_context(|| format!("Failed to open {:?}", flac_path))?;
+    let info = reader.streaminfo();
+    let spec = hound::WavSpec {
+        channels: info.channels as u16,
+        sample_rate: info.sample_rate,
+        bits_per_sample: info.bits_per_sample as u16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(wav_path, spec)
+        .with_context(|| format!("Failed to create {:?}", wav_path))?;
+    for sample in reader.samples() {
+        writer.write_sample(sample.context("Corrupt FLAC archive")?)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Deletes archived audio older than `max_age_days`, then the oldest files
+/// until the archive fits in `max_total_mb`. Either limit can be 0 to disable
+/// it. Returns how many recordings were removed.
+pub fn enforce_retention(db: &Database, max_age_days: u32, max_total_mb: u32) -> Result<usize> {
+    let now = db::current_timestamp();
+    let max_age_secs = max_age_days as i64 * 24 * 60 * 60;
+    let max_total_bytes = max_total_mb as u64 * 1024 * 1024;
+
+    let mut total_bytes = 0u64;
+    let mut removed = 0;
+    // Newest first, so the size limit keeps the most recent recordings.
+    for (session_id, started_at, path) in db.get_archived_audio()? {
+        let size = fs::metadata(&path).map(|m| m.len()).ok();
+        total_bytes += size.unwrap_or(0);
+
+        let too_old = max_age_days > 0 && now - started_at > max_age_secs;
+        let over_budget = max_total_mb > 0 && total_bytes > max_total_bytes;
+        if size.is_some() && !too_old && !over_budget {
+            continue;
+        }
+
+        if size.is_some() {
+            fs::remove_file(&path).with_context(|| format!("Failed to delete {:?}", path))?;
+            removed += 1;
+        }
+        db.clear_audio_path(session_id)?;
+    }
+    Ok(removed)
+}
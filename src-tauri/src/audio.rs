@@ -3,16 +3,111 @@ use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Sample, Stream};
 use dasp_sample::FromSample;
 use hound::{WavSpec, WavWriter};
-use std::sync::mpsc::{self, Receiver, Sender};
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use crate::tempfiles::TempRecording;
+use crate::AppEvent;
 
+/// Sample rate whisper.cpp expects its input WAV files to use.
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Ring buffer capacity, in seconds of mono audio at the capture rate.
+const RING_BUFFER_SECS: usize = 2;
+
+/// How long the consumer sleeps when the ring buffer is empty.
+const CONSUMER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Zero crossings of the sinc kernel on each side of the resampling point.
+const RESAMPLER_ZERO_CROSSINGS: f64 = 8.0;
+
+/// Resolution of the precomputed resampling kernel, in entries per input sample.
+const RESAMPLER_TABLE_STEPS: f64 = 512.0;
+
+/// Energy-based endpointing settings: recording stops once the RMS level has
+/// stayed below `threshold` for `silence_ms` after speech was heard.
+#[derive(Debug, Clone, Copy)]
+pub struct VadSettings {
+    pub threshold: f32,
+    pub silence_ms: u32,
+}
+
+struct SilenceDetector {
+    threshold: f32,
+    silence_frames_limit: usize,
+    silent_frames: usize,
+    heard_speech: bool,
+    fired: bool,
+    event_tx: tokio::sync::mpsc::Sender<AppEvent>,
+}
+
+impl SilenceDetector {
+    fn new(settings: VadSettings, sample_rate: u32, event_tx: tokio::sync::mpsc::Sender<AppEvent>) -> Self {
+        let silence_frames_limit = (sample_rate as u64 * settings.silence_ms as u64 / 1000) as usize;
+        Self {
+            threshold: settings.threshold,
+            silence_frames_limit,
+            silent_frames: 0,
+            heard_speech: false,
+            fired: false,
+            event_tx,
+        }
+    }
+
+    /// Feed the RMS of a block of `frames` mono frames. Runs on the audio
+    /// callback thread, so it must never block.
+    fn observe(&mut self, rms: f32, frames: usize) {
+        if self.fired {
+            return;
+        }
+        if rms >= self.threshold {
+            self.heard_speech = true;
+            self.silent_frames = 0;
+            return;
+        }
+        if !self.heard_speech {
+            return;
+        }
+        self.silent_frames += frames;
+        if self.silent_frames >= self.silence_frames_limit {
+            self.fired = true;
+            let _ = self.event_tx.try_send(AppEvent::SilenceDetected);
+        }
+    }
+}
+
+/// State shared between the real-time input callback and the recorder.
+struct CaptureShared {
+    level_bits: AtomicU32,
+    dropped_samples: AtomicUsize,
+}
+
+type SpoolWriter = WavWriter<BufWriter<File>>;
+
+/// Capture side of the recording pipeline. The cpal callback only downmixes
+/// into a preallocated SPSC ring buffer; a consumer thread drains it,
+/// resamples to 16 kHz and writes the WAV file as it goes, so the real-time
+/// thread never allocates or takes a lock and memory use does not grow with
+/// the length of the recording.
 pub struct AudioRecorder {
     stream: Stream,
-    receiver: Receiver<f32>,
-    level_receiver: Receiver<f32>,
+    shared: Arc<CaptureShared>,
+    consumer_thread: JoinHandle<Result<()>>,
+    /// The WAV file the consumer writes into, in the private temp directory.
+    spool: TempRecording,
 }
 
 impl AudioRecorder {
-    pub fn start_recording(device_name: Option<&str>) -> Result<Self> {
+    pub fn start_recording(
+        device_name: Option<&str>,
+        vad: Option<VadSettings>,
+        event_tx: tokio::sync::mpsc::Sender<AppEvent>,
+    ) -> Result<Self> {
         let host = cpal::default_host();
 
         let device = if let Some(name) = device_name {
@@ -27,26 +122,24 @@ impl AudioRecorder {
 
         let user_config = cpal::StreamConfig {
             channels: 1,
-            sample_rate: cpal::SampleRate(16000),
+            sample_rate: cpal::SampleRate(WHISPER_SAMPLE_RATE),
             buffer_size: cpal::BufferSize::Default,
         };
 
-        let (sender, receiver) = mpsc::channel();
-        let (level_sender, level_receiver) = mpsc::channel();
+        let shared = Arc::new(CaptureShared {
+            level_bits: AtomicU32::new(0),
+            dropped_samples: AtomicUsize::new(0),
+        });
+        let spool = TempRecording::create()?;
+        let writer = WavWriter::create(spool.path(), whisper_wav_spec())?;
 
-        let level_sender_clone = level_sender.clone();
+        let (mut producer, consumer) = RingBuffer::new(WHISPER_SAMPLE_RATE as usize * RING_BUFFER_SECS);
+        let shared_clone = shared.clone();
+        let mut detector = vad.map(|v| SilenceDetector::new(v, WHISPER_SAMPLE_RATE, event_tx.clone()));
         let stream = match device.build_input_stream(
             &user_config,
             move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let mut sum_sq = 0.0f32;
-                for &sample in data {
-                    let _ = sender.send(sample);
-                    sum_sq += sample * sample;
-                }
-                if !data.is_empty() {
-                    let rms = (sum_sq / data.len() as f32).sqrt();
-                    let _ = level_sender_clone.send(rms);
-                }
+                Self::write_input_data(data, 1, &mut producer, &shared_clone, &mut detector)
             },
             |err| eprintln!("an error occurred on stream: {}", err),
             None,
@@ -54,27 +147,35 @@ impl AudioRecorder {
             Ok(s) => s,
             Err(_) => {
                 let default_config = device.default_input_config()?;
-                let (sender, new_receiver) = mpsc::channel();
-                let (level_sender, new_level_receiver) = mpsc::channel();
-                let stream = Self::build_fallback_stream(&device, &default_config, sender, level_sender)?;
+                let sample_rate = default_config.sample_rate().0;
+                let (producer, consumer) = RingBuffer::new(sample_rate as usize * RING_BUFFER_SECS);
+                let detector = vad.map(|v| SilenceDetector::new(v, sample_rate, event_tx));
+                let stream = Self::build_fallback_stream(&device, &default_config, producer, shared.clone(), detector)?;
                 return Ok(Self {
                     stream,
-                    receiver: new_receiver,
-                    level_receiver: new_level_receiver,
+                    shared,
+                    consumer_thread: Self::spawn_consumer(consumer, sample_rate, writer),
+                    spool,
                 });
             }
         };
 
         stream.play()?;
 
-        Ok(Self { stream, receiver, level_receiver })
+        Ok(Self {
+            stream,
+            shared,
+            consumer_thread: Self::spawn_consumer(consumer, WHISPER_SAMPLE_RATE, writer),
+            spool,
+        })
     }
 
     fn build_fallback_stream(
         device: &cpal::Device,
         config: &cpal::SupportedStreamConfig,
-        sender: Sender<f32>,
-        level_sender: Sender<f32>,
+        mut producer: Producer<f32>,
+        shared: Arc<CaptureShared>,
+        mut detector: Option<SilenceDetector>,
     ) -> Result<Stream> {
         let channels = config.channels();
 
@@ -82,19 +183,19 @@ impl AudioRecorder {
         let stream = match config.sample_format() {
             cpal::SampleFormat::F32 => device.build_input_stream(
                 &stream_config,
-                move |data: &[f32], _: &_| Self::write_input_data(data, channels, &sender, &level_sender),
+                move |data: &[f32], _: &_| Self::write_input_data(data, channels, &mut producer, &shared, &mut detector),
                 |err| eprintln!("an error occurred on stream: {}", err),
                 None,
             )?,
             cpal::SampleFormat::I16 => device.build_input_stream(
                 &stream_config,
-                move |data: &[i16], _: &_| Self::write_input_data(data, channels, &sender, &level_sender),
+                move |data: &[i16], _: &_| Self::write_input_data(data, channels, &mut producer, &shared, &mut detector),
                 |err| eprintln!("an error occurred on stream: {}", err),
                 None,
             )?,
             cpal::SampleFormat::U16 => device.build_input_stream(
                 &stream_config,
-                move |data: &[u16], _: &_| Self::write_input_data(data, channels, &sender, &level_sender),
+                move |data: &[u16], _: &_| Self::write_input_data(data, channels, &mut producer, &shared, &mut detector),
                 |err| eprintln!("an error occurred on stream: {}", err),
                 None,
             )?,
@@ -106,61 +207,236 @@ impl AudioRecorder {
         Ok(stream)
     }
 
-    fn write_input_data<T>(input: &[T], channels: u16, sender: &Sender<f32>, level_sender: &Sender<f32>)
+    /// Runs on the real-time audio thread: no allocation, no locks, no blocking.
+    fn write_input_data<T>(
+        input: &[T],
+        channels: u16,
+        producer: &mut Producer<f32>,
+        shared: &CaptureShared,
+        detector: &mut Option<SilenceDetector>,
+    )
     where
         T: Sample,
         f32: FromSample<T>,
     {
         let mut sum_sq = 0.0f32;
         let mut count = 0usize;
+        let mut dropped = 0usize;
         for frame in input.chunks(channels as usize) {
             let mut sum = 0.0;
             for &sample in frame {
                 sum += f32::from_sample_(sample);
             }
             let mono = sum / channels as f32;
-            let _ = sender.send(mono);
+            if producer.push(mono).is_err() {
+                dropped += 1;
+            }
             sum_sq += mono * mono;
             count += 1;
         }
+        if dropped > 0 {
+            shared.dropped_samples.fetch_add(dropped, Ordering::Relaxed);
+        }
         if count > 0 {
             let rms = (sum_sq / count as f32).sqrt();
-            let _ = level_sender.send(rms);
+            shared.level_bits.store(rms.to_bits(), Ordering::Relaxed);
+            if let Some(d) = detector.as_mut() {
+                d.observe(rms, count);
+            }
         }
     }
 
-    pub fn drain_levels(&self) -> f32 {
-        let mut last = 0.0f32;
-        while let Ok(level) = self.level_receiver.try_recv() {
-            last = level;
-        }
-        last
+    /// Drains the ring buffer into `writer` until the producer side is dropped
+    /// together with the stream. After a write error the ring buffer is still
+    /// drained, so the callback keeps running, and the error is returned at the end.
+    fn spawn_consumer(mut consumer: Consumer<f32>, sample_rate: u32, mut writer: SpoolWriter) -> JoinHandle<Result<()>> {
+        std::thread::spawn(move || {
+            let mut resampler = Resampler::new(sample_rate, WHISPER_SAMPLE_RATE);
+            let mut resampled = Vec::new();
+            let mut result = Ok(());
+            loop {
+                let available = consumer.slots();
+                if available > 0 {
+                    if let Ok(chunk) = consumer.read_chunk(available) {
+                        let (first, second) = chunk.as_slices();
+                        resampler.process(first, &mut resampled);
+                        resampler.process(second, &mut resampled);
+                        chunk.commit_all();
+                    }
+                } else if consumer.is_abandoned() {
+                    break;
+                } else {
+                    std::thread::sleep(CONSUMER_POLL_INTERVAL);
+                }
+                if result.is_ok() {
+                    result = write_samples(&mut writer, &resampled);
+                }
+                resampled.clear();
+            }
+            resampler.finish(&mut resampled);
+            result?;
+            write_samples(&mut writer, &resampled)?;
+            writer.finalize()?;
+            Ok(())
+        })
+    }
+
+    pub fn current_level(&self) -> f32 {
+        f32::from_bits(self.shared.level_bits.load(Ordering::Relaxed))
     }
 
-    pub fn stop_recording_and_save(self, save_path: &str) -> Result<()> {
+    /// Stops capturing and throws the recorded audio away.
+    pub fn discard(self) {
         let _ = self.stream.pause();
+        drop(self.stream);
+        let _ = self.consumer_thread.join();
+    }
+
+    pub fn stop_recording_and_save(self, save_path: &Path) -> Result<()> {
+        let _ = self.stream.pause();
+        // Dropping the stream drops the producer, which lets the consumer finish.
+        drop(self.stream);
 
-        let mut samples: Vec<f32> = Vec::new();
-        while let Ok(sample) = self.receiver.try_recv() {
-            samples.push(sample);
+        self.consumer_thread.join()
+            .map_err(|_| anyhow!("Audio consumer thread panicked"))??;
+
+        let dropped = self.shared.dropped_samples.load(Ordering::Relaxed);
+        if dropped > 0 {
+            eprintln!("Warning: capture ring buffer overflowed, {} samples dropped", dropped);
         }
 
-        let spec = WavSpec {
-            channels: 1,
-            sample_rate: 16000,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
+        // Both files live in the private temp directory, so this is normally a rename.
+        if fs::rename(self.spool.path(), save_path).is_err() {
+            fs::copy(self.spool.path(), save_path)?;
+        }
+        Ok(())
+    }
+}
+
+fn whisper_wav_spec() -> WavSpec {
+    WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    }
+}
+
+fn write_samples(writer: &mut SpoolWriter, samples: &[f32]) -> Result<()> {
+    let amplitude = i16::MAX as f32;
+    for &sample in samples {
+        writer.write_sample((sample * amplitude).clamp(-amplitude, amplitude) as i16)?;
+    }
+    Ok(())
+}
+
+/// Converts mono audio between sample rates with a Hann-windowed sinc filter.
+/// When downsampling, the filter cutoff is lowered to the target Nyquist
+/// frequency so content above it is removed instead of aliasing. Input can
+/// arrive in chunks of any size; only the samples still under the kernel are kept.
+pub struct Resampler {
+    ratio: f64,
+    half_width: f64,
+    /// The kernel for t >= 0 (it is symmetric), sampled `RESAMPLER_TABLE_STEPS`
+    /// times per input sample. Empty when the rates are equal.
+    table: Vec<f64>,
+    /// Input not yet fully used; `pending[0]` is input sample number `offset`.
+    pending: Vec<f32>,
+    offset: usize,
+    /// Index of the next output sample.
+    next: usize,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let ratio = to_rate as f64 / from_rate as f64;
+        let cutoff = ratio.min(1.0);
+        let half_width = RESAMPLER_ZERO_CROSSINGS / cutoff;
+        let table = if from_rate == to_rate {
+            Vec::new()
+        } else {
+            let table_len = (half_width * RESAMPLER_TABLE_STEPS).ceil() as usize + 2;
+            (0..table_len)
+                .map(|i| {
+                    let t = i as f64 / RESAMPLER_TABLE_STEPS;
+                    if t >= half_width {
+                        return 0.0;
+                    }
+                    let window = 0.5 * (1.0 + (std::f64::consts::PI * t / half_width).cos());
+                    sinc(cutoff * t) * window
+                })
+                .collect()
         };
+        Self { ratio, half_width, table, pending: Vec::new(), offset: 0, next: 0 }
+    }
 
-        let mut writer = WavWriter::create(save_path, spec)?;
-        for sample in samples {
-            let amplitude = i16::MAX as f32;
-            let i16_sample = (sample * amplitude).clamp(-amplitude, amplitude) as i16;
-            writer.write_sample(i16_sample)?;
+    /// Appends every output sample whose kernel is fully covered by the input so far.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.table.is_empty() {
+            output.extend_from_slice(input);
+            return;
+        }
+        self.pending.extend_from_slice(input);
+        let received = self.offset + self.pending.len();
+        loop {
+            let center = self.next as f64 / self.ratio;
+            let hi = (center + self.half_width).floor() as usize;
+            if hi >= received {
+                break;
+            }
+            output.push(self.interpolate(center, hi));
+            self.next += 1;
         }
-        writer.finalize()?;
 
-        Ok(())
+        let center = self.next as f64 / self.ratio;
+        let lo = (center - self.half_width).ceil().max(0.0) as usize;
+        let used = lo.saturating_sub(self.offset).min(self.pending.len());
+        self.pending.drain(..used);
+        self.offset += used;
+    }
+
+    /// Appends the remaining output once the input has ended.
+    pub fn finish(&mut self, output: &mut Vec<f32>) {
+        let received = self.offset + self.pending.len();
+        if self.table.is_empty() || received == 0 {
+            return;
+        }
+        let out_len = (received as f64 * self.ratio).round() as usize;
+        while self.next < out_len {
+            let center = self.next as f64 / self.ratio;
+            let hi = ((center + self.half_width).floor() as usize).min(received - 1);
+            output.push(self.interpolate(center, hi));
+            self.next += 1;
+        }
+    }
+
+    /// Output sample centred on input position `center`, using input up to `hi`.
+    fn interpolate(&self, center: f64, hi: usize) -> f32 {
+        let lo = (center - self.half_width).ceil().max(0.0) as usize;
+        let mut acc = 0.0f64;
+        let mut weight_sum = 0.0f64;
+        for k in lo..=hi {
+            let weight = self.kernel(k as f64 - center);
+            acc += self.pending[k - self.offset] as f64 * weight;
+            weight_sum += weight;
+        }
+        if weight_sum.abs() > f64::EPSILON { (acc / weight_sum) as f32 } else { 0.0 }
+    }
+
+    fn kernel(&self, t: f64) -> f64 {
+        let pos = t.abs() * RESAMPLER_TABLE_STEPS;
+        let i = pos as usize;
+        let frac = pos - i as f64;
+        self.table[i] + (self.table[i + 1] - self.table[i]) * frac
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
     }
 }
 
@@ -175,3 +451,150 @@ pub fn list_input_devices() -> Result<Vec<String>> {
     }
     Ok(names)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+        let mut resampler = Resampler::new(from_rate, to_rate);
+        let mut output = Vec::new();
+        resampler.process(input, &mut output);
+        resampler.finish(&mut output);
+        output
+    }
+
+    fn sine(rate: u32, freq: f32, secs: f32) -> Vec<f32> {
+        let len = (rate as f32 * secs) as usize;
+        (0..len)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    /// Peak amplitude away from the edges, where the kernel is truncated.
+    fn inner_peak(samples: &[f32]) -> f32 {
+        let edge = samples.len() / 10;
+        samples[edge..samples.len() - edge].iter().fold(0.0f32, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn resamples_48k_to_16k() {
+        let input = sine(48000, 440.0, 1.0);
+        let output = resample(&input, 48000, WHISPER_SAMPLE_RATE);
+        assert_eq!(output.len(), 16000);
+        assert!((inner_peak(&output) - 0.5).abs() < 0.02, "peak {}", inner_peak(&output));
+    }
+
+    #[test]
+    fn resamples_44k1_to_16k() {
+        let input = sine(44100, 440.0, 2.0);
+        let output = resample(&input, 44100, WHISPER_SAMPLE_RATE);
+        assert_eq!(output.len(), 32000);
+        assert!((inner_peak(&output) - 0.5).abs() < 0.02, "peak {}", inner_peak(&output));
+    }
+
+    #[test]
+    fn removes_content_above_target_nyquist() {
+        // 12 kHz would alias to 4 kHz at 16 kHz if it were not filtered out.
+        let input = sine(48000, 12000.0, 1.0);
+        let output = resample(&input, 48000, WHISPER_SAMPLE_RATE);
+        assert!(inner_peak(&output) < 0.01, "peak {}", inner_peak(&output));
+    }
+
+    #[test]
+    fn same_rate_is_unchanged() {
+        let input = sine(16000, 440.0, 0.5);
+        assert_eq!(resample(&input, 16000, 16000), input);
+    }
+
+    #[test]
+    fn empty_input_stays_empty() {
+        assert!(resample(&[], 48000, WHISPER_SAMPLE_RATE).is_empty());
+    }
+
+    #[test]
+    fn chunked_input_matches_whole_input() {
+        let input = sine(44100, 440.0, 1.0);
+        let mut resampler = Resampler::new(44100, WHISPER_SAMPLE_RATE);
+        let mut output = Vec::new();
+        // Chunks smaller than the kernel, and an empty one.
+        for chunk in input.chunks(7) {
+            resampler.process(chunk, &mut output);
+        }
+        resampler.process(&[], &mut output);
+        resampler.finish(&mut output);
+        assert_eq!(output, resample(&input, 44100, WHISPER_SAMPLE_RATE));
+    }
+
+    /// Callback time and memory for a 10-minute recording through the fallback
+    /// path (48 kHz stereo, 10 ms callbacks) into the consumer and WAV writer.
+    /// Run with `cargo test --release -- --ignored --nocapture capture_benchmark`.
+    #[test]
+    #[ignore]
+    fn capture_benchmark() {
+        const RATE: u32 = 48000;
+        const CHANNELS: u16 = 2;
+        const FRAMES_PER_CALLBACK: usize = 480;
+        const SECS: usize = 600;
+
+        let path = std::env::temp_dir().join(format!("voiceclip-benchmark-{}.wav", std::process::id()));
+        let writer = WavWriter::create(&path, whisper_wav_spec()).unwrap();
+        let (mut producer, consumer) = RingBuffer::new(RATE as usize * RING_BUFFER_SECS);
+        let shared = CaptureShared { level_bits: AtomicU32::new(0), dropped_samples: AtomicUsize::new(0) };
+        let (event_tx, _event_rx) = tokio::sync::mpsc::channel(1);
+        let vad = VadSettings { threshold: 0.01, silence_ms: 1000 };
+        let mut detector = Some(SilenceDetector::new(vad, RATE, event_tx));
+        let consumer_thread = AudioRecorder::spawn_consumer(consumer, RATE, writer);
+        let rss_before = resident_kb();
+
+        let callbacks = RATE as usize * SECS / FRAMES_PER_CALLBACK;
+        let mut times = Vec::with_capacity(callbacks);
+        let mut input = vec![0.0f32; FRAMES_PER_CALLBACK * CHANNELS as usize];
+        let mut rss_peak = rss_before;
+        for n in 0..callbacks {
+            for (i, frame) in input.chunks_mut(CHANNELS as usize).enumerate() {
+                let t = (n * FRAMES_PER_CALLBACK + i) as f32 / RATE as f32;
+                frame.fill(0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin());
+            }
+            // A real device delivers in real time; here the consumer sets the pace.
+            while producer.slots() < FRAMES_PER_CALLBACK {
+                std::thread::yield_now();
+            }
+            let start = std::time::Instant::now();
+            AudioRecorder::write_input_data(&input, CHANNELS, &mut producer, &shared, &mut detector);
+            times.push(start.elapsed());
+            if n % 6000 == 0 {
+                rss_peak = rss_peak.max(resident_kb());
+            }
+        }
+        drop(producer);
+        consumer_thread.join().unwrap().unwrap();
+        rss_peak = rss_peak.max(resident_kb());
+
+        times.sort();
+        let mean = times.iter().sum::<Duration>() / times.len() as u32;
+        let file_kb = fs::metadata(&path).unwrap().len() / 1024;
+        let _ = fs::remove_file(&path);
+        println!("{} callbacks of {} frames", callbacks, FRAMES_PER_CALLBACK);
+        println!(
+            "callback time: mean {:?}, p99 {:?}, max {:?} (budget {:?})",
+            mean,
+            times[times.len() * 99 / 100],
+            times[times.len() - 1],
+            Duration::from_secs_f64(FRAMES_PER_CALLBACK as f64 / RATE as f64)
+        );
+        match (rss_before, rss_peak) {
+            (Some(before), Some(peak)) => println!("resident memory growth: {} kB", peak - before),
+            _ => println!("resident memory: not available on this platform"),
+        }
+        println!("WAV written: {} kB", file_kb);
+        assert_eq!(shared.dropped_samples.load(Ordering::Relaxed), 0);
+    }
+
+    /// `VmRSS` from /proc, in kB.
+    fn resident_kb() -> Option<u64> {
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    }
+}
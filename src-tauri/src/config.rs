@@ -1,40 +1,349 @@
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// Upper bound for `transcription_timeout_factor`; even large models on slow
+/// CPUs run well below 100 times real time.
+pub const MAX_TIMEOUT_FACTOR: f32 = 100.0;
+
+/// How the global hotkey drives recording.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyMode {
+    /// Press once to start, press again to stop.
+    #[default]
+    Toggle,
+    /// Record while the chord is held down, transcribe on release.
+    Hold,
+}
+
+/// Speech-to-text engine used for transcription.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriberBackend {
+    /// Run whisper.cpp's `whisper-cli` for every recording.
+    #[default]
+    WhisperCli,
+    /// Keep a whisper-server process running so the model stays loaded.
+    WhisperServer,
+    /// Return canned text without running a model (for testing).
+    Mock,
+}
+
+/// Language and decoding options passed through to whisper.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct WhisperOptions {
+    /// Spoken language as an ISO 639-1 code, or `"auto"` to detect it.
+    pub language: String,
+    /// Translate the speech to English instead of transcribing it.
+    pub translate: bool,
+    /// `None` leaves the value to whisper's own default.
+    pub threads: Option<u32>,
+    pub beam_size: Option<u32>,
+    pub best_of: Option<u32>,
+    pub temperature: Option<f32>,
+}
+
+impl Default for WhisperOptions {
+    fn default() -> Self {
+        Self {
+            language: "auto".to_string(),
+            translate: false,
+            threads: None,
+            beam_size: None,
+            best_of: None,
+            temperature: None,
+        }
+    }
+}
+
+/// Rules for dropping text whisper produces on silence or noise.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FilterOptions {
+    pub enabled: bool,
+    /// Segments matching one of these phrases, ignoring case and punctuation, are dropped.
+    pub denylist: Vec<String>,
+    /// Regexes whose matches are removed from the text.
+    pub patterns: Vec<String>,
+    /// Segments whisper rates as more likely than this to contain no speech, and
+    /// decoded with low confidence, are dropped.
+    /// Set to 1.0 to disable.
+    pub no_speech_threshold: f32,
+}
+
+impl Default for FilterOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            denylist: [
+                "Thank you for watching",
+                "Thanks for watching",
+                "Please subscribe",
+                "Like and subscribe",
+                "Subtitles by the Amara.org community",
+            ]
+            .map(String::from)
+            .to_vec(),
+            patterns: [
+                r"(?i)\[\s*(blank_audio|music|silence|inaudible|noise|applause|laughter|sound)[^\]]*\]",
+                r"(?i)\(\s*(music|silence|inaudible|noise|applause|laughter|static|upbeat music)[^)]*\)",
+                r"[♪♫]+",
+            ]
+            .map(String::from)
+            .to_vec(),
+            no_speech_threshold: 0.6,
+        }
+    }
+}
+
+/// A text transform in the `postprocess.steps` chain.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PostProcessStep {
+    /// Remove leading and trailing whitespace.
+    Trim,
+    /// Collapse runs of whitespace and remove spaces before punctuation.
+    NormalizeWhitespace,
+    /// Remove `filler_words` along with a comma that follows them.
+    RemoveFillers,
+    /// Uppercase the first letter of each sentence.
+    Capitalize,
+    /// End the text with a period unless it already ends in punctuation.
+    AddTrailingPeriod,
+    /// Drop a single trailing period, e.g. for search boxes or chat.
+    RemoveTrailingPeriod,
+    /// Turn spoken commands such as "comma" or "new line" into text, using
+    /// the grammar for the transcript's language.
+    SpokenPunctuation,
+}
+
+/// Spoken commands for one language.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SpokenGrammar {
+    /// Said before a command to type its words instead, e.g. "literal comma".
+    pub escape: String,
+    /// Spoken phrase to inserted text. Spaces in the text are kept, so ", "
+    /// attaches to the previous word and " (" to the next one.
+    pub commands: BTreeMap<String, String>,
+}
+
+impl Default for SpokenGrammar {
+    fn default() -> Self {
+        Self { escape: "literal".to_string(), commands: BTreeMap::new() }
+    }
+}
+
+fn grammar(escape: &str, commands: &[(&str, &str)]) -> SpokenGrammar {
+    SpokenGrammar {
+        escape: escape.to_string(),
+        commands: commands.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+    }
+}
+
+fn default_spoken_grammars() -> BTreeMap<String, SpokenGrammar> {
+    let mut grammars = BTreeMap::new();
+    grammars.insert("en".to_string(), grammar("literal", &[
+        ("new line", "\n"),
+        ("new paragraph", "\n\n"),
+        ("bullet point", "\n- "),
+        ("comma", ", "),
+        ("period", ". "),
+        ("full stop", ". "),
+        ("question mark", "? "),
+        ("exclamation mark", "! "),
+        ("colon", ": "),
+        ("semicolon", "; "),
+        ("dash", " - "),
+        ("open quote", " \""),
+        ("close quote", "\" "),
+        ("open paren", " ("),
+        ("close paren", ") "),
+    ]));
+    grammars.insert("pt".to_string(), grammar("literal", &[
+        ("nova linha", "\n"),
+        ("novo parágrafo", "\n\n"),
+        ("marcador", "\n- "),
+        ("vírgula", ", "),
+        ("ponto final", ". "),
+        ("ponto de interrogação", "? "),
+        ("ponto de exclamação", "! "),
+        ("dois pontos", ": "),
+        ("ponto e vírgula", "; "),
+        ("travessão", " - "),
+        ("abre aspas", " \""),
+        ("fecha aspas", "\" "),
+        ("abre parênteses", " ("),
+        ("fecha parênteses", ") "),
+    ]));
+    grammars.insert("es".to_string(), grammar("literal", &[
+        ("nueva línea", "\n"),
+        ("nuevo párrafo", "\n\n"),
+        ("viñeta", "\n- "),
+        ("coma", ", "),
+        ("punto final", ". "),
+        ("signo de interrogación", "? "),
+        ("signo de exclamación", "! "),
+        ("dos puntos", ": "),
+        ("punto y coma", "; "),
+        ("guion", " - "),
+        ("abrir comillas", " \""),
+        ("cerrar comillas", "\" "),
+        ("abrir paréntesis", " ("),
+        ("cerrar paréntesis", ") "),
+    ]));
+    grammars
+}
+
+/// Transforms applied to the text before it is delivered, in order.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PostProcessOptions {
+    pub steps: Vec<PostProcessStep>,
+    /// Matched as whole words, ignoring case.
+    pub filler_words: Vec<String>,
+    /// Grammars for the `spoken_punctuation` step, keyed by ISO 639-1 code.
+    pub spoken_punctuation: BTreeMap<String, SpokenGrammar>,
+    /// Said before a snippet's trigger, e.g. "snippet sign off".
+    pub snippet_keyword: String,
+}
+
+impl Default for PostProcessOptions {
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                PostProcessStep::RemoveFillers,
+                PostProcessStep::NormalizeWhitespace,
+                PostProcessStep::Trim,
+                PostProcessStep::Capitalize,
+            ],
+            filler_words: ["um", "umm", "uh", "uhh", "er", "erm", "hmm"].map(String::from).to_vec(),
+            spoken_punctuation: default_spoken_grammars(),
+            snippet_keyword: "snippet".to_string(),
+        }
+    }
+}
+
+/// Action triggered by a chord in the `[keybindings]` table.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Start/stop recording; follows `hotkey_mode` like the main hotkey.
+    Toggle,
+    /// Stop recording and discard the audio.
+    Cancel,
+    /// Copy the last successful transcription to the clipboard again.
+    RecopyLast,
+    /// Flip `append_mode`.
+    ToggleAppend,
+    /// Open the stats window.
+    OpenStats,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct Config {
+    pub transcriber: TranscriberBackend,
+    /// Loopback port for whisper-server; 0 picks a free one.
+    pub whisper_server_port: u16,
     pub model_name: String,
+    /// Models tried in order when `model_name` is missing, fails or times out.
+    pub model_fallbacks: Vec<String>,
+    pub whisper: WhisperOptions,
+    pub filter: FilterOptions,
+    pub postprocess: PostProcessOptions,
     pub hotkey: String,
+    pub hotkey_mode: HotkeyMode,
     pub badge_opacity: f32,
     pub max_recording_duration: u32,
+    /// Fixed allowance, in seconds, for a transcription to finish. 0 disables the timeout.
+    pub transcription_timeout_secs: u32,
+    /// Extra seconds allowed per second of recorded audio, up to [`MAX_TIMEOUT_FACTOR`].
+    pub transcription_timeout_factor: f32,
     pub append_mode: bool,
+    /// Archive every recording as FLAC so it can be played back or re-transcribed.
+    pub keep_audio: bool,
+    /// Archived audio older than this many days is deleted. 0 keeps it forever.
+    pub audio_retention_days: u32,
+    /// Oldest archived audio is deleted once the archive grows past this many MB. 0 means no limit.
+    pub audio_retention_max_mb: u32,
     pub microphone: Option<String>,
     pub always_on_top: bool,
     pub badge_x: Option<i32>,
     pub badge_y: Option<i32>,
+    pub vad_enabled: bool,
+    pub vad_threshold: f32,
+    pub vad_silence_ms: u32,
+    /// Extra chords mapped to actions, e.g. `"Ctrl+Alt+X" = "cancel"`.
+    pub keybindings: BTreeMap<String, HotkeyAction>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            transcriber: TranscriberBackend::WhisperCli,
+            whisper_server_port: 0,
             model_name: "base.en".to_string(),
+            model_fallbacks: Vec::new(),
+            whisper: WhisperOptions::default(),
+            filter: FilterOptions::default(),
+            postprocess: PostProcessOptions::default(),
             hotkey: "Super+Alt+V".to_string(),
+            hotkey_mode: HotkeyMode::Toggle,
             badge_opacity: 0.8,
             max_recording_duration: 120,
+            transcription_timeout_secs: 30,
+            transcription_timeout_factor: 3.0,
             append_mode: false,
+            keep_audio: false,
+            audio_retention_days: 30,
+            audio_retention_max_mb: 500,
             microphone: None,
             always_on_top: true,
             badge_x: None,
             badge_y: None,
+            vad_enabled: false,
+            vad_threshold: 0.01,
+            vad_silence_ms: 2000,
+            keybindings: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
+    /// Every chord the hotkey listener should bind: the main hotkey followed by `[keybindings]`.
+    pub fn hotkey_bindings(&self) -> Vec<(String, HotkeyAction)> {
+        let mut bindings = vec![(self.hotkey.clone(), HotkeyAction::Toggle)];
+        bindings.extend(self.keybindings.iter().map(|(chord, action)| (chord.clone(), *action)));
+        bindings
+    }
+
+    /// How long a transcription of `audio_secs` of audio may run before it is
+    /// killed. A factor too large to represent, e.g. from a hand-edited file,
+    /// means no limit.
+    pub fn transcription_timeout(&self, audio_secs: u32) -> Option<Duration> {
+        if self.transcription_timeout_secs == 0 {
+            return None;
+        }
+        let scaled = Duration::try_from_secs_f32(audio_secs as f32 * self.transcription_timeout_factor.max(0.0)).ok()?;
+        Duration::from_secs(self.transcription_timeout_secs as u64).checked_add(scaled)
+    }
+
+    /// Fails if `transcription_timeout_factor` is not a number from 0 to [`MAX_TIMEOUT_FACTOR`].
+    pub fn validate_timeout(&self) -> Result<()> {
+        let factor = self.transcription_timeout_factor;
+        if !(0.0..=MAX_TIMEOUT_FACTOR).contains(&factor) {
+            anyhow::bail!("The timeout factor must be between 0 and {}, got {}", MAX_TIMEOUT_FACTOR, factor);
+        }
+        Ok(())
+    }
+
     pub fn get_config_dir() -> Result<PathBuf> {
         let proj_dirs = ProjectDirs::from("", "", "voiceclip")
             .context("Could not find project directories")?;
@@ -55,6 +364,20 @@ impl Config {
         Ok(proj_dirs.data_local_dir().join("models"))
     }
 
+    /// Archived recordings kept when `keep_audio` is on.
+    pub fn get_audio_dir() -> Result<PathBuf> {
+        let proj_dirs = ProjectDirs::from("", "", "voiceclip")
+            .context("Could not find project directories")?;
+        Ok(proj_dirs.data_local_dir().join("audio"))
+    }
+
+    /// Recordings whose transcription failed, kept so they can be retried.
+    pub fn get_failed_dir() -> Result<PathBuf> {
+        let proj_dirs = ProjectDirs::from("", "", "voiceclip")
+            .context("Could not find project directories")?;
+        Ok(proj_dirs.data_local_dir().join("failed"))
+    }
+
     pub fn get_db_path() -> Result<PathBuf> {
         let proj_dirs = ProjectDirs::from("", "", "voiceclip")
             .context("Could not find project directories")?;
@@ -97,3 +420,38 @@ impl Config {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_timeout(secs: u32, factor: f32) -> Config {
+        Config { transcription_timeout_secs: secs, transcription_timeout_factor: factor, ..Config::default() }
+    }
+
+    #[test]
+    fn timeout_grows_with_the_recording() {
+        assert_eq!(with_timeout(30, 3.0).transcription_timeout(20), Some(Duration::from_secs(90)));
+        assert_eq!(with_timeout(30, 0.0).transcription_timeout(20), Some(Duration::from_secs(30)));
+        assert_eq!(with_timeout(30, -2.0).transcription_timeout(20), Some(Duration::from_secs(30)));
+        assert_eq!(with_timeout(0, 3.0).transcription_timeout(20), None);
+    }
+
+    #[test]
+    fn unrepresentable_factors_mean_no_limit() {
+        for factor in [f32::INFINITY, f32::MAX, 1e30] {
+            assert_eq!(with_timeout(30, factor).transcription_timeout(600), None, "{}", factor);
+        }
+        assert_eq!(with_timeout(30, f32::NAN).transcription_timeout(600), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn factor_must_be_finite_and_in_range() {
+        for factor in [0.0, 3.0, MAX_TIMEOUT_FACTOR] {
+            assert!(with_timeout(30, factor).validate_timeout().is_ok(), "{}", factor);
+        }
+        for factor in [-1.0, MAX_TIMEOUT_FACTOR + 1.0, f32::INFINITY, f32::NAN] {
+            assert!(with_timeout(30, factor).validate_timeout().is_err(), "{}", factor);
+        }
+    }
+}
@@ -1,18 +1,125 @@
 use anyhow::{Context, Result};
 use rusqlite::{params, Connection};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
+use crate::transcript::{Transcript, Word};
+
+/// Why a session failed, stored in the `error_kind` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Failed,
+    /// The transcription was killed for running too long.
+    Timeout,
+}
+
+impl ErrorKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Failed => "failed",
+            ErrorKind::Timeout => "timeout",
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
+pub struct SessionError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct SessionRecord {
     pub started_at: i64,
     pub duration_secs: u32,
     pub word_count: u32,
     pub model_used: String,
+    /// Name of the fallback model that produced the text because the configured one failed.
+    pub fallback_model: Option<String>,
+    /// Delivered text, after filtering and post-processing.
     pub transcription: String,
+    /// Text exactly as whisper returned it, kept to audit post-processing.
+    pub raw_transcription: Option<String>,
     pub latency_ms: u32,
-    pub error: Option<String>,
+    pub truncated: bool,
+    pub cancelled: bool,
+    /// The filter removed everything whisper returned; `transcription` holds the raw text.
+    pub no_speech: bool,
+    /// JSON of the `WhisperOptions` the transcription ran with.
+    pub whisper_options: Option<String>,
+    /// Segment timings and word confidences, stored in the `segments` table.
+    pub transcript: Option<Transcript>,
+    /// Recording kept in the `failed/` directory so the session can be retried.
+    pub failed_audio_path: Option<String>,
+    /// Compressed copy of the recording when `keep_audio` is on.
+    pub audio_path: Option<String>,
+    pub error: Option<SessionError>,
+}
+
+impl SessionRecord {
+    /// A session with no transcription, model or outcome yet.
+    pub fn new(started_at: i64, duration_secs: u32, truncated: bool) -> Self {
+        Self { started_at, duration_secs, truncated, ..Self::default() }
+    }
+}
+
+/// An alternate transcription of a session's recording, e.g. with a larger model.
+#[derive(Debug, Clone)]
+pub struct TranscriptRecord {
+    pub session_id: i64,
+    pub model_used: String,
+    pub latency_ms: u32,
+    pub whisper_options: Option<String>,
+    pub transcript: Transcript,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptRow {
+    pub id: i64,
+    pub session_id: i64,
+    pub created_at: i64,
+    pub model_used: String,
+    pub transcription: String,
+    pub latency_ms: u32,
+    pub whisper_options: Option<String>,
+    pub language: Option<String>,
+    pub avg_logprob: Option<f64>,
+}
+
+/// A word or phrase whisper should be primed with.
+#[derive(Debug, Clone, Serialize)]
+pub struct VocabularyTerm {
+    pub id: i64,
+    pub term: String,
+    /// Language code the term applies to, or `"*"` for every language.
+    pub language: String,
+}
+
+/// A find/replace rule applied to every transcription before post-processing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacementRule {
+    /// Ignored when adding a rule.
+    #[serde(default)]
+    pub id: i64,
+    /// Literal text, or a regex when `is_regex` is set.
+    pub pattern: String,
+    /// Inserted as-is for literal rules; regex rules may use `$1` or `${name}`.
+    pub replacement: String,
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    /// Only match whole words, so "get hub" does not match inside "forget hubris".
+    pub whole_word: bool,
+    pub enabled: bool,
+}
+
+/// Boilerplate text inserted when its trigger is spoken after the snippet keyword.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: i64,
+    /// Matched ignoring case, e.g. "sign off".
+    pub trigger: String,
+    /// May contain `{date}`, `{time}` and `{clipboard}`.
+    pub body: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -30,7 +137,31 @@ pub struct SessionRow {
     pub word_count: u32,
     pub transcription: String,
     pub latency_ms: u32,
+    pub truncated: bool,
+    pub cancelled: bool,
+    pub no_speech: bool,
+    /// JSON of the `WhisperOptions` the transcription ran with.
+    pub whisper_options: Option<String>,
+    pub language: Option<String>,
+    pub avg_logprob: Option<f64>,
     pub error: Option<String>,
+    /// `"failed"` or `"timeout"` when `error` is set.
+    pub error_kind: Option<String>,
+    pub failed_audio_path: Option<String>,
+    pub audio_path: Option<String>,
+    pub model_used: String,
+    pub fallback_model: Option<String>,
+    pub raw_transcription: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SegmentRow {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    pub avg_logprob: Option<f64>,
+    pub no_speech_prob: Option<f64>,
+    pub words: Vec<Word>,
 }
 
 pub struct Database {
@@ -48,6 +179,8 @@ impl Database {
 
         let conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open SQLite database at {:?}", db_path))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .context("Failed to enable foreign keys")?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS sessions (
@@ -63,13 +196,89 @@ impl Database {
             [],
         ).context("Failed to initialize database schema")?;
 
+        add_column_if_missing(&conn, "sessions", "truncated", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "sessions", "cancelled", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "sessions", "whisper_options", "TEXT")?;
+        add_column_if_missing(&conn, "sessions", "language", "TEXT")?;
+        add_column_if_missing(&conn, "sessions", "no_speech", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "sessions", "error_kind", "TEXT")?;
+        add_column_if_missing(&conn, "sessions", "failed_audio_path", "TEXT")?;
+        add_column_if_missing(&conn, "sessions", "audio_path", "TEXT")?;
+        add_column_if_missing(&conn, "sessions", "avg_logprob", "REAL")?;
+        add_column_if_missing(&conn, "sessions", "fallback_model", "TEXT")?;
+        add_column_if_missing(&conn, "sessions", "raw_transcription", "TEXT")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS segments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                start_ms INTEGER NOT NULL,
+                end_ms INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                avg_logprob REAL,
+                no_speech_prob REAL,
+                words TEXT NOT NULL DEFAULT '[]'
+            );
+            CREATE INDEX IF NOT EXISTS segments_session_id ON segments(session_id);",
+        ).context("Failed to initialize segments table")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS transcripts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+                created_at INTEGER NOT NULL,
+                model_used TEXT NOT NULL,
+                transcription TEXT NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                whisper_options TEXT,
+                language TEXT,
+                avg_logprob REAL
+            );
+            CREATE INDEX IF NOT EXISTS transcripts_session_id ON transcripts(session_id);",
+        ).context("Failed to initialize transcripts table")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS vocabulary (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                term TEXT NOT NULL,
+                language TEXT NOT NULL DEFAULT '*',
+                UNIQUE(term, language)
+            )",
+            [],
+        ).context("Failed to initialize vocabulary table")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS replacement_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                pattern TEXT NOT NULL,
+                replacement TEXT NOT NULL,
+                is_regex INTEGER NOT NULL DEFAULT 0,
+                case_sensitive INTEGER NOT NULL DEFAULT 0,
+                whole_word INTEGER NOT NULL DEFAULT 1,
+                enabled INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        ).context("Failed to initialize replacement rules table")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS snippets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trigger TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                body TEXT NOT NULL
+            )",
+            [],
+        ).context("Failed to initialize snippets table")?;
+
         Ok(Self { conn })
     }
 
-    pub fn log_session(&self, session: SessionRecord) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO sessions (started_at, duration_secs, word_count, model_used, transcription, latency_ms, error)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    pub fn log_session(&self, session: SessionRecord) -> Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        let transcript = session.transcript.as_ref();
+        tx.execute(
+            "INSERT INTO sessions (started_at, duration_secs, word_count, model_used, transcription, latency_ms, truncated, cancelled, no_speech, whisper_options, language, avg_logprob, error, error_kind, failed_audio_path, audio_path, fallback_model, raw_transcription)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
             params![
                 session.started_at,
                 session.duration_secs,
@@ -77,17 +286,161 @@ impl Database {
                 session.model_used,
                 session.transcription,
                 session.latency_ms,
-                session.error,
+                session.truncated,
+                session.cancelled,
+                session.no_speech,
+                session.whisper_options,
+                transcript.and_then(|t| t.language.as_deref()),
+                transcript.and_then(|t| t.avg_logprob),
+                session.error.as_ref().map(|e| e.message.as_str()),
+                session.error.as_ref().map(|e| e.kind.as_str()),
+                session.failed_audio_path,
+                session.audio_path,
+                session.fallback_model,
+                session.raw_transcription,
             ],
         ).context("Failed to insert session record")?;
+        let session_id = tx.last_insert_rowid();
+        if let Some(transcript) = transcript {
+            insert_segments(&tx, session_id, transcript)?;
+        }
+        tx.commit()?;
+
+        Ok(session_id)
+    }
+
+    /// Overwrites the outcome of a session after it was transcribed again.
+    /// Timing fields (`started_at`, `duration_secs`, `truncated`) are kept, and
+    /// so is `audio_path` unless the record sets a new one.
+    pub fn update_session(&self, session_id: i64, session: SessionRecord) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let transcript = session.transcript.as_ref();
+        tx.execute(
+            "UPDATE sessions SET word_count = ?2, model_used = ?3, transcription = ?4, latency_ms = ?5,
+                cancelled = ?6, no_speech = ?7, whisper_options = ?8, language = ?9, avg_logprob = ?10,
+                error = ?11, error_kind = ?12, failed_audio_path = ?13, audio_path = COALESCE(?14, audio_path),
+                fallback_model = ?15, raw_transcription = ?16
+             WHERE id = ?1",
+            params![
+                session_id,
+                session.word_count,
+                session.model_used,
+                session.transcription,
+                session.latency_ms,
+                session.cancelled,
+                session.no_speech,
+                session.whisper_options,
+                transcript.and_then(|t| t.language.as_deref()),
+                transcript.and_then(|t| t.avg_logprob),
+                session.error.as_ref().map(|e| e.message.as_str()),
+                session.error.as_ref().map(|e| e.kind.as_str()),
+                session.failed_audio_path,
+                session.audio_path,
+                session.fallback_model,
+                session.raw_transcription,
+            ],
+        ).context("Failed to update session record")?;
+        tx.execute("DELETE FROM segments WHERE session_id = ?1", params![session_id])?;
+        if let Some(transcript) = transcript {
+            insert_segments(&tx, session_id, transcript)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_session(&self, session_id: i64) -> Result<Option<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM sessions WHERE id = ?1", SESSION_ROW_COLUMNS))?;
+        let mut rows = stmt.query(params![session_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(session_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn add_transcript(&self, record: TranscriptRecord) -> Result<TranscriptRow> {
+        self.conn.execute(
+            "INSERT INTO transcripts (session_id, created_at, model_used, transcription, latency_ms, whisper_options, language, avg_logprob)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.session_id,
+                current_timestamp(),
+                record.model_used,
+                record.transcript.text,
+                record.latency_ms,
+                record.whisper_options,
+                record.transcript.language,
+                record.transcript.avg_logprob,
+            ],
+        ).context("Failed to insert transcript")?;
+        let id = self.conn.last_insert_rowid();
+        let row = self.conn.query_row(
+            &format!("SELECT {} FROM transcripts WHERE id = ?1", TRANSCRIPT_ROW_COLUMNS),
+            params![id],
+            transcript_row,
+        )?;
+        Ok(row)
+    }
+
+    /// Alternate transcripts of a session, oldest first.
+    pub fn get_transcripts(&self, session_id: i64) -> Result<Vec<TranscriptRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM transcripts WHERE session_id = ?1 ORDER BY created_at, id", TRANSCRIPT_ROW_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![session_id], transcript_row)?;
+        let mut transcripts = Vec::new();
+        for row in rows {
+            transcripts.push(row?);
+        }
+        Ok(transcripts)
+    }
+
+    /// `(session id, started_at, path)` of every archived recording, newest first.
+    pub fn get_archived_audio(&self) -> Result<Vec<(i64, i64, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, started_at, audio_path FROM sessions
+             WHERE audio_path IS NOT NULL ORDER BY started_at DESC, id DESC"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        let mut audio = Vec::new();
+        for row in rows {
+            audio.push(row?);
+        }
+        Ok(audio)
+    }
 
+    pub fn clear_audio_path(&self, session_id: i64) -> Result<()> {
+        self.conn.execute("UPDATE sessions SET audio_path = NULL WHERE id = ?1", params![session_id])
+            .context("Failed to clear audio path")?;
         Ok(())
     }
 
+    pub fn get_session_segments(&self, session_id: i64) -> Result<Vec<SegmentRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT start_ms, end_ms, text, avg_logprob, no_speech_prob, words
+             FROM segments WHERE session_id = ?1 ORDER BY position"
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            let words: String = row.get(5)?;
+            Ok(SegmentRow {
+                start_ms: row.get(0)?,
+                end_ms: row.get(1)?,
+                text: row.get(2)?,
+                avg_logprob: row.get(3)?,
+                no_speech_prob: row.get(4)?,
+                words: serde_json::from_str(&words).unwrap_or_default(),
+            })
+        })?;
+        let mut segments = Vec::new();
+        for row in rows {
+            segments.push(row?);
+        }
+        Ok(segments)
+    }
+
     pub fn get_stats_summary(&self) -> Result<StatsSummary> {
         let mut stmt = self.conn.prepare(
             "SELECT COUNT(*), COALESCE(SUM(duration_secs),0), COALESCE(AVG(word_count),0)
-             FROM sessions WHERE error IS NULL"
+             FROM sessions WHERE error IS NULL AND cancelled = 0 AND no_speech = 0"
         )?;
         let summary = stmt.query_row([], |row| {
             Ok(StatsSummary {
@@ -99,22 +452,186 @@ impl Database {
         Ok(summary)
     }
 
-    pub fn get_recent_sessions(&self, limit: u32) -> Result<Vec<SessionRow>> {
+    pub fn list_vocabulary(&self) -> Result<Vec<VocabularyTerm>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, started_at, duration_secs, word_count, transcription, latency_ms, error
-             FROM sessions ORDER BY started_at DESC LIMIT ?"
+            "SELECT id, term, language FROM vocabulary ORDER BY language, id"
         )?;
-        let rows = stmt.query_map(params![limit], |row| {
-            Ok(SessionRow {
+        let rows = stmt.query_map([], |row| {
+            Ok(VocabularyTerm {
                 id: row.get(0)?,
-                started_at: row.get(1)?,
-                duration_secs: row.get(2)?,
-                word_count: row.get(3)?,
-                transcription: row.get(4)?,
-                latency_ms: row.get(5)?,
-                error: row.get(6)?,
+                term: row.get(1)?,
+                language: row.get(2)?,
             })
         })?;
+        let mut terms = Vec::new();
+        for row in rows {
+            terms.push(row?);
+        }
+        Ok(terms)
+    }
+
+    /// Terms for `language`, language-specific ones first, then the ones shared by all languages.
+    pub fn get_vocabulary_for_language(&self, language: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT term FROM vocabulary WHERE language = ?1 OR language = '*'
+             ORDER BY language = '*', id"
+        )?;
+        let rows = stmt.query_map(params![language], |row| row.get(0))?;
+        let mut terms = Vec::new();
+        for row in rows {
+            terms.push(row?);
+        }
+        Ok(terms)
+    }
+
+    pub fn add_vocabulary_term(&self, term: &str, language: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO vocabulary (term, language) VALUES (?1, ?2)",
+            params![term, language],
+        ).context("Failed to insert vocabulary term")?;
+        let id = self.conn.query_row(
+            "SELECT id FROM vocabulary WHERE term = ?1 AND language = ?2",
+            params![term, language],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    pub fn remove_vocabulary_term(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM vocabulary WHERE id = ?1", params![id])
+            .context("Failed to delete vocabulary term")?;
+        Ok(())
+    }
+
+    /// All rules in the order they are applied.
+    pub fn list_rules(&self) -> Result<Vec<ReplacementRule>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, pattern, replacement, is_regex, case_sensitive, whole_word, enabled
+             FROM replacement_rules ORDER BY id"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ReplacementRule {
+                id: row.get(0)?,
+                pattern: row.get(1)?,
+                replacement: row.get(2)?,
+                is_regex: row.get(3)?,
+                case_sensitive: row.get(4)?,
+                whole_word: row.get(5)?,
+                enabled: row.get(6)?,
+            })
+        })?;
+        let mut rules = Vec::new();
+        for row in rows {
+            rules.push(row?);
+        }
+        Ok(rules)
+    }
+
+    pub fn add_rule(&self, rule: &ReplacementRule) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO replacement_rules (pattern, replacement, is_regex, case_sensitive, whole_word, enabled)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![rule.pattern, rule.replacement, rule.is_regex, rule.case_sensitive, rule.whole_word, rule.enabled],
+        ).context("Failed to insert replacement rule")?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_rule(&self, rule: &ReplacementRule) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE replacement_rules SET pattern = ?2, replacement = ?3, is_regex = ?4,
+                case_sensitive = ?5, whole_word = ?6, enabled = ?7
+             WHERE id = ?1",
+            params![rule.id, rule.pattern, rule.replacement, rule.is_regex, rule.case_sensitive, rule.whole_word, rule.enabled],
+        ).context("Failed to update replacement rule")?;
+        if updated == 0 {
+            anyhow::bail!("Replacement rule {} not found", rule.id);
+        }
+        Ok(())
+    }
+
+    pub fn remove_rule(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM replacement_rules WHERE id = ?1", params![id])
+            .context("Failed to delete replacement rule")?;
+        Ok(())
+    }
+
+    pub fn list_snippets(&self) -> Result<Vec<Snippet>> {
+        let mut stmt = self.conn.prepare("SELECT id, trigger, body FROM snippets ORDER BY trigger")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Snippet {
+                id: row.get(0)?,
+                trigger: row.get(1)?,
+                body: row.get(2)?,
+            })
+        })?;
+        let mut snippets = Vec::new();
+        for row in rows {
+            snippets.push(row?);
+        }
+        Ok(snippets)
+    }
+
+    /// Fails if another snippet already uses `trigger`.
+    pub fn add_snippet(&self, trigger: &str, body: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO snippets (trigger, body) VALUES (?1, ?2)",
+            params![trigger, body],
+        ).with_context(|| format!("Failed to add snippet '{}'; is the trigger already in use?", trigger))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_snippet(&self, snippet: &Snippet) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE snippets SET trigger = ?2, body = ?3 WHERE id = ?1",
+            params![snippet.id, snippet.trigger, snippet.body],
+        ).with_context(|| format!("Failed to update snippet '{}'; is the trigger already in use?", snippet.trigger))?;
+        if updated == 0 {
+            anyhow::bail!("Snippet {} not found", snippet.id);
+        }
+        Ok(())
+    }
+
+    pub fn remove_snippet(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM snippets WHERE id = ?1", params![id])
+            .context("Failed to delete snippet")?;
+        Ok(())
+    }
+
+    pub fn get_last_transcription(&self) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT transcription FROM sessions
+             WHERE error IS NULL AND cancelled = 0 AND no_speech = 0 AND transcription != ''
+             ORDER BY started_at DESC, id DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query([])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The latest sessions that produced text and still have whisper's raw
+    /// output, newest first. Cancelled, failed and no-speech sessions are skipped.
+    pub fn get_recent_transcriptions(&self, limit: u32) -> Result<Vec<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM sessions
+             WHERE cancelled = 0 AND error IS NULL AND no_speech = 0 AND raw_transcription IS NOT NULL
+             ORDER BY started_at DESC, id DESC LIMIT ?",
+            SESSION_ROW_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![limit], session_row)?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            sessions.push(row?);
+        }
+        Ok(sessions)
+    }
+
+    pub fn get_recent_sessions(&self, limit: u32) -> Result<Vec<SessionRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM sessions ORDER BY started_at DESC LIMIT ?", SESSION_ROW_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![limit], session_row)?;
         let mut sessions = Vec::new();
         for row in rows {
             sessions.push(row?);
@@ -123,9 +640,117 @@ impl Database {
     }
 }
 
+/// Columns read by [`session_row`], in order.
+const SESSION_ROW_COLUMNS: &str = "id, started_at, duration_secs, word_count, transcription, latency_ms, truncated, cancelled, no_speech, whisper_options, language, avg_logprob, error, error_kind, failed_audio_path, audio_path, model_used, fallback_model, raw_transcription";
+
+fn session_row(row: &rusqlite::Row) -> rusqlite::Result<SessionRow> {
+    Ok(SessionRow {
+        id: row.get(0)?,
+        started_at: row.get(1)?,
+        duration_secs: row.get(2)?,
+        word_count: row.get(3)?,
+        transcription: row.get(4)?,
+        latency_ms: row.get(5)?,
+        truncated: row.get(6)?,
+        cancelled: row.get(7)?,
+        no_speech: row.get(8)?,
+        whisper_options: row.get(9)?,
+        language: row.get(10)?,
+        avg_logprob: row.get(11)?,
+        error: row.get(12)?,
+        error_kind: row.get(13)?,
+        failed_audio_path: row.get(14)?,
+        audio_path: row.get(15)?,
+        model_used: row.get(16)?,
+        fallback_model: row.get(17)?,
+        raw_transcription: row.get(18)?,
+    })
+}
+
+const TRANSCRIPT_ROW_COLUMNS: &str = "id, session_id, created_at, model_used, transcription, latency_ms, whisper_options, language, avg_logprob";
+
+fn transcript_row(row: &rusqlite::Row) -> rusqlite::Result<TranscriptRow> {
+    Ok(TranscriptRow {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        created_at: row.get(2)?,
+        model_used: row.get(3)?,
+        transcription: row.get(4)?,
+        latency_ms: row.get(5)?,
+        whisper_options: row.get(6)?,
+        language: row.get(7)?,
+        avg_logprob: row.get(8)?,
+    })
+}
+
+fn insert_segments(conn: &Connection, session_id: i64, transcript: &Transcript) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO segments (session_id, position, start_ms, end_ms, text, avg_logprob, no_speech_prob, words)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+    )?;
+    for (position, segment) in transcript.segments.iter().enumerate() {
+        let words: Vec<&Word> = transcript.segment_words(position).collect();
+        stmt.execute(params![
+            session_id,
+            position as i64,
+            segment.start_ms,
+            segment.end_ms,
+            segment.text,
+            segment.avg_logprob,
+            segment.no_speech_prob,
+            serde_json::to_string(&words)?,
+        ]).context("Failed to insert transcript segment")?;
+    }
+    Ok(())
+}
+
+/// Adds a column to an existing table so databases created by older versions keep working.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])
+            .with_context(|| format!("Failed to add column {}.{}", table, column))?;
+    }
+    Ok(())
+}
+
 pub fn current_timestamp() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs() as i64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_transcriptions_skip_cancelled_sessions_before_the_limit() {
+        let path = std::env::temp_dir().join(format!("voiceclip-db-test-{}.sqlite", std::process::id()));
+        let db = Database::new(&path).unwrap();
+        for i in 0..60 {
+            let session = SessionRecord {
+                transcription: format!("Text {}.", i),
+                raw_transcription: Some(format!("text {}", i)),
+                // The newest sessions are the ones that would crowd out real text.
+                cancelled: i >= 40,
+                ..SessionRecord::new(1_000 + i, 1, false)
+            };
+            db.log_session(session).unwrap();
+        }
+        db.log_session(SessionRecord { transcription: "legacy".to_string(), ..SessionRecord::new(2_000, 1, false) })
+            .unwrap();
+
+        let sessions = db.get_recent_transcriptions(30).unwrap();
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(sessions.len(), 30);
+        assert!(sessions.iter().all(|s| !s.cancelled && s.raw_transcription.is_some()));
+        assert_eq!(sessions[0].raw_transcription.as_deref(), Some("text 39"));
+    }
+}
@@ -14,6 +14,11 @@ pub fn copy_to_clipboard(text: &str, append_mode: bool) -> Result<()> {
     Ok(())
 }
 
+pub fn read_clipboard() -> Result<String> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    Ok(clipboard.get_text()?)
+}
+
 pub fn notify(title: &str, body: &str, _is_error: bool) -> Result<()> {
     let mut notification = notify_rust::Notification::new();
     notification.summary(title).body(body);
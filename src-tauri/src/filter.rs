@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use crate::config::FilterOptions;
+use crate::transcript::{Segment, Transcript, Word};
+
+/// Like whisper's own `logprob_threshold`: a segment decoded with at least this
+/// confidence is kept even when whisper thinks it may be silence.
+const LOGPROB_THRESHOLD: f32 = -1.0;
+
+/// Removes text whisper makes up on silence or background noise, such as
+/// `[BLANK_AUDIO]`, `(music)` or "Thank you for watching."
+pub struct TranscriptFilter {
+    enabled: bool,
+    /// Normalized with [`normalize`].
+    denylist: Vec<String>,
+    patterns: Vec<Regex>,
+    no_speech_threshold: f32,
+}
+
+impl TranscriptFilter {
+    /// Fails if one of the patterns is not a valid regex.
+    pub fn new(options: &FilterOptions) -> Result<Self> {
+        let patterns = options
+            .patterns
+            .iter()
+            .map(|p| Regex::new(p).with_context(|| format!("Invalid filter pattern '{}'", p)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            enabled: options.enabled,
+            denylist: options.denylist.iter().map(|d| normalize(d)).filter(|d| !d.is_empty()).collect(),
+            patterns,
+            no_speech_threshold: options.no_speech_threshold,
+        })
+    }
+
+    /// Drops segments that are likely silence or hallucinated, and strips
+    /// pattern matches from the rest. An empty `text` means no speech remained.
+    pub fn apply(&self, transcript: &Transcript) -> Transcript {
+        if !self.enabled {
+            return transcript.clone();
+        }
+
+        let mut filtered = Transcript {
+            language: transcript.language.clone(),
+            avg_logprob: transcript.avg_logprob,
+            ..Transcript::default()
+        };
+
+        if transcript.segments.is_empty() {
+            filtered.text = self.clean(&transcript.text).unwrap_or_default();
+            return filtered;
+        }
+
+        for (index, segment) in transcript.segments.iter().enumerate() {
+            if self.is_silence(segment) {
+                continue;
+            }
+            let Some(text) = self.clean(&segment.text) else {
+                continue;
+            };
+            let position = filtered.segments.len();
+            filtered.words.extend(
+                transcript
+                    .segment_words(index)
+                    .filter(|w| self.clean(&w.text).is_some())
+                    .map(|w| Word { segment: position, ..w.clone() }),
+            );
+            let mut segment = segment.clone();
+            segment.text = text;
+            filtered.segments.push(segment);
+        }
+
+        let text = filtered.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+        match self.clean(&text) {
+            Some(text) => filtered.text = text,
+            None => {
+                filtered.segments.clear();
+                filtered.words.clear();
+            }
+        }
+        filtered
+    }
+
+    /// Follows whisper's rule: likely silence *and* decoded with low confidence.
+    /// whisper-cli does not report `no_speech_prob`, so this only applies to whisper-server.
+    fn is_silence(&self, segment: &Segment) -> bool {
+        segment.no_speech_prob.is_some_and(|p| p > self.no_speech_threshold)
+            && segment.avg_logprob.is_some_and(|l| l < LOGPROB_THRESHOLD)
+    }
+
+    /// Strips pattern matches; `None` if nothing is left or the remainder is denylisted.
+    fn clean(&self, text: &str) -> Option<String> {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, "").into_owned();
+        }
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let normalized = normalize(&text);
+        if normalized.is_empty() || self.denylist.contains(&normalized) {
+            None
+        } else {
+            Some(text)
+        }
+    }
+}
+
+/// Lowercases and reduces punctuation to single spaces, so "Thanks for watching!"
+/// matches a denylist entry of "thanks for watching".
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, no_speech_prob: Option<f32>, avg_logprob: Option<f32>) -> Segment {
+        Segment { start_ms: 0, end_ms: 1000, text: text.to_string(), avg_logprob, no_speech_prob }
+    }
+
+    /// One word per whitespace-separated token, with the text joined like whisper's.
+    fn transcript(segments: Vec<Segment>) -> Transcript {
+        let words = segments
+            .iter()
+            .enumerate()
+            .flat_map(|(index, s)| {
+                s.text.split_whitespace().map(move |w| Word {
+                    segment: index,
+                    text: w.to_string(),
+                    start_ms: 0,
+                    end_ms: 0,
+                    probability: 0.9,
+                })
+            })
+            .collect();
+        let text = segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+        Transcript { text, segments, words, ..Transcript::default() }
+    }
+
+    fn default_filter() -> TranscriptFilter {
+        TranscriptFilter::new(&FilterOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn denylisted_segments_are_dropped_ignoring_case_and_punctuation() {
+        let raw = transcript(vec![
+            segment("Hello there.", None, None),
+            segment("THANKS for watching!", None, None),
+            segment("Bye.", None, None),
+        ]);
+        let filtered = default_filter().apply(&raw);
+        assert_eq!(filtered.text, "Hello there. Bye.");
+        assert_eq!(filtered.segments.len(), 2);
+        let words: Vec<_> = filtered.words.iter().map(|w| (w.segment, w.text.as_str())).collect();
+        assert_eq!(words, [(0, "Hello"), (0, "there."), (1, "Bye.")]);
+    }
+
+    #[test]
+    fn patterns_are_stripped_from_the_text() {
+        let cases = [
+            ("[BLANK_AUDIO] Hello", "Hello"),
+            ("Hello (upbeat music) world", "Hello world"),
+            ("♪♪ Sing along ♫", "Sing along"),
+            ("[ Music playing ] Done", "Done"),
+            ("Keep [this] and (that)", "Keep [this] and (that)"),
+        ];
+        let filter = default_filter();
+        for (input, expected) in cases {
+            assert_eq!(filter.apply(&transcript(vec![segment(input, None, None)])).text, expected, "{:?}", input);
+        }
+        // Text-only transcripts, such as the mock backend's, are cleaned as a whole.
+        assert_eq!(filter.apply(&Transcript::from_text("(music) Hi")).text, "Hi");
+    }
+
+    #[test]
+    fn silence_needs_both_a_high_no_speech_prob_and_a_low_logprob() {
+        let raw = transcript(vec![
+            segment("dropped", Some(0.9), Some(-1.5)),
+            segment("confident", Some(0.9), Some(-0.3)),
+            segment("unscored", Some(0.9), None),
+            segment("speech", Some(0.3), Some(-1.5)),
+        ]);
+        assert_eq!(default_filter().apply(&raw).text, "confident unscored speech");
+
+        let off = FilterOptions { no_speech_threshold: 1.0, ..FilterOptions::default() };
+        assert_eq!(TranscriptFilter::new(&off).unwrap().apply(&raw).text, "dropped confident unscored speech");
+    }
+
+    #[test]
+    fn nothing_left_means_no_speech() {
+        let raw = transcript(vec![
+            segment("[BLANK_AUDIO]", None, None),
+            segment("Thank you for watching.", None, None),
+            segment("hmm", Some(0.95), Some(-2.0)),
+        ]);
+        let filtered = default_filter().apply(&raw);
+        assert!(filtered.text.is_empty());
+        assert!(filtered.segments.is_empty());
+        assert!(filtered.words.is_empty());
+    }
+
+    #[test]
+    fn disabled_filter_passes_everything_through() {
+        let raw = transcript(vec![segment("[BLANK_AUDIO]", Some(0.99), Some(-3.0))]);
+        let off = FilterOptions { enabled: false, ..FilterOptions::default() };
+        assert_eq!(TranscriptFilter::new(&off).unwrap().apply(&raw).text, "[BLANK_AUDIO]");
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        let options = FilterOptions { patterns: vec!["(unclosed".to_string()], ..FilterOptions::default() };
+        assert!(TranscriptFilter::new(&options).is_err());
+    }
+}
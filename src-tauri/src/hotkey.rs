@@ -1,70 +1,203 @@
 use tokio::sync::mpsc::Sender;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use crate::config::{HotkeyAction, HotkeyMode};
 use crate::AppEvent;
 
+#[derive(Debug, Clone)]
+pub enum HotkeyError {
+    /// The hotkey string is not a recognised chord.
+    Parse { hotkey: String },
+    /// The chord parsed but could not be bound on this system.
+    Register { hotkey: String, message: String },
+    /// The same chord, possibly spelled differently, is bound to two actions.
+    Duplicate { hotkey: String, other: String },
+}
+
+impl fmt::Display for HotkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HotkeyError::Parse { hotkey } => write!(f, "could not parse hotkey '{}'", hotkey),
+            HotkeyError::Register { hotkey, message } => {
+                write!(f, "failed to register hotkey '{}': {}", hotkey, message)
+            }
+            HotkeyError::Duplicate { hotkey, other } => {
+                write!(f, "hotkey '{}' is the same chord as '{}', which is already bound", hotkey, other)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HotkeyError {}
+
+/// Handle to a running hotkey listener. Dropping it unbinds the hotkey and
+/// stops the listener threads.
+pub struct HotkeyListener {
+    stop: Arc<AtomicBool>,
+    #[cfg(target_os = "windows")]
+    registration: Option<(SendManager, Vec<global_hotkey::hotkey::HotKey>)>,
+}
+
+impl HotkeyListener {
+    fn disabled() -> Self {
+        Self {
+            stop: Arc::new(AtomicBool::new(true)),
+            #[cfg(target_os = "windows")]
+            registration: None,
+        }
+    }
+}
+
+impl Drop for HotkeyListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        #[cfg(target_os = "windows")]
+        if let Some((manager, hotkeys)) = self.registration.take() {
+            let _ = manager.0.unregister_all(&hotkeys);
+        }
+    }
+}
+
+fn is_disabled(hotkey: &str) -> bool {
+    hotkey.is_empty() || hotkey.eq_ignore_ascii_case("none")
+}
+
+/// Bindings whose chord is not set to "None", with the chord trimmed.
+fn active_bindings(bindings: &[(String, HotkeyAction)]) -> Vec<(String, HotkeyAction)> {
+    bindings
+        .iter()
+        .map(|(chord, action)| (chord.trim().to_string(), *action))
+        .filter(|(chord, _)| !is_disabled(chord))
+        .collect()
+}
+
+/// Event to send when the chord goes down.
+fn press_event(action: HotkeyAction, mode: HotkeyMode) -> AppEvent {
+    match action {
+        HotkeyAction::Toggle => match mode {
+            HotkeyMode::Toggle => AppEvent::ToggleRecording,
+            HotkeyMode::Hold => AppEvent::StartRecording,
+        },
+        HotkeyAction::Cancel => AppEvent::Cancel,
+        HotkeyAction::RecopyLast => AppEvent::RecopyLast,
+        HotkeyAction::ToggleAppend => AppEvent::ToggleAppendMode,
+        HotkeyAction::OpenStats => AppEvent::OpenStats,
+    }
+}
+
+/// Event to send when the chord is released, if any.
+fn release_event(action: HotkeyAction, mode: HotkeyMode) -> Option<AppEvent> {
+    (action == HotkeyAction::Toggle && mode == HotkeyMode::Hold).then_some(AppEvent::StopRecording)
+}
+
+/// Checks that every chord parses and that no two bindings share a chord,
+/// without binding anything.
+pub fn validate_bindings(bindings: &[(String, HotkeyAction)]) -> Result<(), HotkeyError> {
+    let mut seen: Vec<(String, _)> = Vec::new();
+    for (chord, _) in active_bindings(bindings) {
+        #[cfg(target_os = "linux")]
+        let parsed = parse_hotkey_evdev(&chord);
+        #[cfg(target_os = "windows")]
+        let parsed = parse_hotkey(&chord).map(|hotkey| hotkey.id());
+        let Some(parsed) = parsed else {
+            return Err(HotkeyError::Parse { hotkey: chord });
+        };
+        if let Some((other, _)) = seen.iter().find(|(_, p)| *p == parsed) {
+            return Err(HotkeyError::Duplicate { hotkey: chord, other: other.clone() });
+        }
+        seen.push((chord, parsed));
+    }
+    Ok(())
+}
+
 #[cfg(target_os = "linux")]
-pub fn start_hotkey_listener(tx: Sender<AppEvent>, configured_hotkey: &str) {
+pub fn start_hotkey_listener(
+    tx: Sender<AppEvent>,
+    bindings: &[(String, HotkeyAction)],
+    mode: HotkeyMode,
+) -> Result<HotkeyListener, HotkeyError> {
     use evdev::{EventType, KeyCode};
     use std::collections::HashSet;
 
-    let hotkey_trimmed = configured_hotkey.trim();
-    if hotkey_trimmed.is_empty() || hotkey_trimmed.eq_ignore_ascii_case("none") {
-        eprintln!("Hotkey disabled (set to '{}'), badge click still works.", configured_hotkey);
-        return;
+    let bindings = active_bindings(bindings);
+    if bindings.is_empty() {
+        eprintln!("Hotkeys disabled, badge click still works.");
+        return Ok(HotkeyListener::disabled());
     }
 
-    let parsed = match parse_hotkey_evdev(hotkey_trimmed) {
-        Some(p) => p,
-        None => {
-            eprintln!("Warning: could not parse hotkey '{}'. Hotkey disabled, badge click still works.", configured_hotkey);
-            return;
-        }
-    };
+    let mut parsed = Vec::new();
+    for (chord, action) in &bindings {
+        let hotkey = parse_hotkey_evdev(chord)
+            .ok_or_else(|| HotkeyError::Parse { hotkey: chord.clone() })?;
+        parsed.push((hotkey, *action));
+    }
 
-    let devices = evdev::enumerate().filter(|(_, d)| d.supported_keys().is_some());
+    let devices: Vec<_> = evdev::enumerate().filter(|(_, d)| d.supported_keys().is_some()).collect();
+    if devices.is_empty() {
+        return Err(HotkeyError::Register {
+            hotkey: bindings[0].0.clone(),
+            message: "no readable keyboard devices (is the user in the 'input' group?)".to_string(),
+        });
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
 
     for (_path, mut device) in devices {
         let tx_clone = tx.clone();
         let parsed = parsed.clone();
+        let stop = stop.clone();
 
+        // A replaced listener stays blocked in fetch_events until the next key
+        // event on its device, then sees the stop flag and exits without sending.
         std::thread::spawn(move || {
             let mut pressed_keys = HashSet::new();
+            let mut chord_active = vec![false; parsed.len()];
+
+            while let Ok(events) = device.fetch_events() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                for event in events {
+                    if event.event_type() != EventType::KEY {
+                        continue;
+                    }
+                    let key = KeyCode::new(event.code());
+                    if event.value() == 1 {
+                        pressed_keys.insert(key);
+                    } else if event.value() == 0 {
+                        pressed_keys.remove(&key);
+                    }
 
-            loop {
-                match device.fetch_events() {
-                    Ok(events) => {
-                        for event in events {
-                            if event.event_type() == EventType::KEY {
-                                let key = KeyCode::new(event.code());
-                                if event.value() == 1 {
-                                    pressed_keys.insert(key);
-                                } else if event.value() == 0 {
-                                    pressed_keys.remove(&key);
-                                }
-
-                                let all_modifiers_pressed = parsed.modifiers.iter().all(|mod_pair| {
-                                    pressed_keys.contains(&mod_pair.0) || pressed_keys.contains(&mod_pair.1)
-                                });
-
-                                let trigger_pressed = pressed_keys.contains(&parsed.trigger);
-
-                                if all_modifiers_pressed && trigger_pressed && event.value() == 1 && key == parsed.trigger {
-                                    let _ = tx_clone.blocking_send(AppEvent::ToggleRecording);
-                                }
+                    for (i, (hotkey, action)) in parsed.iter().enumerate() {
+                        let all_modifiers_pressed = hotkey.modifiers.iter().all(|mod_pair| {
+                            pressed_keys.contains(&mod_pair.0) || pressed_keys.contains(&mod_pair.1)
+                        });
+
+                        let trigger_pressed = pressed_keys.contains(&hotkey.trigger);
+
+                        if all_modifiers_pressed && trigger_pressed && event.value() == 1 && key == hotkey.trigger {
+                            chord_active[i] = true;
+                            let _ = tx_clone.blocking_send(press_event(*action, mode));
+                        } else if chord_active[i] && !(all_modifiers_pressed && trigger_pressed) {
+                            chord_active[i] = false;
+                            if let Some(event) = release_event(*action, mode) {
+                                let _ = tx_clone.blocking_send(event);
                             }
                         }
                     }
-                    Err(_) => {
-                        break;
-                    }
                 }
             }
         });
     }
+
+    Ok(HotkeyListener { stop })
 }
 
 #[cfg(target_os = "linux")]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 struct ParsedEvdevHotkey {
+    /// Sorted, so "Ctrl+Shift" and "Shift+Control" compare equal.
     modifiers: Vec<(evdev::KeyCode, evdev::KeyCode)>,
     trigger: evdev::KeyCode,
 }
@@ -86,6 +219,8 @@ fn parse_hotkey_evdev(s: &str) -> Option<ParsedEvdevHotkey> {
         let pair = modifier_name_to_evdev_pair(m)?;
         modifiers.push(pair);
     }
+    modifiers.sort();
+    modifiers.dedup();
 
     Some(ParsedEvdevHotkey { modifiers, trigger })
 }
@@ -160,49 +295,78 @@ fn key_name_to_evdev(name: &str) -> Option<evdev::KeyCode> {
 }
 
 #[cfg(target_os = "windows")]
-pub fn start_hotkey_listener(tx: Sender<AppEvent>, configured_hotkey: &str) {
-    use global_hotkey::{GlobalHotKeyManager, GlobalHotKeyEvent, hotkey::{HotKey, Modifiers, Code}};
+struct SendManager(global_hotkey::GlobalHotKeyManager);
+#[cfg(target_os = "windows")]
+unsafe impl Send for SendManager {}
 
-    let hotkey_trimmed = configured_hotkey.trim();
-    if hotkey_trimmed.is_empty() || hotkey_trimmed.eq_ignore_ascii_case("none") {
-        eprintln!("Hotkey disabled (set to '{}'), badge click still works.", configured_hotkey);
-        return;
+#[cfg(target_os = "windows")]
+pub fn start_hotkey_listener(
+    tx: Sender<AppEvent>,
+    bindings: &[(String, HotkeyAction)],
+    mode: HotkeyMode,
+) -> Result<HotkeyListener, HotkeyError> {
+    use global_hotkey::{GlobalHotKeyManager, GlobalHotKeyEvent, HotKeyState};
+    use std::collections::HashMap;
+
+    let bindings = active_bindings(bindings);
+    if bindings.is_empty() {
+        eprintln!("Hotkeys disabled, badge click still works.");
+        return Ok(HotkeyListener::disabled());
     }
 
-    let hotkey = match parse_hotkey(hotkey_trimmed) {
-        Some(hk) => hk,
-        None => {
-            eprintln!("Warning: could not parse hotkey '{}'. Hotkey disabled, badge click still works.", configured_hotkey);
-            return;
-        }
-    };
+    let mut parsed = Vec::new();
+    for (chord, action) in &bindings {
+        let hotkey = parse_hotkey(chord)
+            .ok_or_else(|| HotkeyError::Parse { hotkey: chord.clone() })?;
+        parsed.push((chord.clone(), hotkey, *action));
+    }
 
-    let manager = match GlobalHotKeyManager::new() {
-        Ok(m) => m,
-        Err(e) => {
-            eprintln!("Warning: failed to create hotkey manager: {}. Hotkey disabled, badge click still works.", e);
-            return;
-        }
-    };
+    let manager = GlobalHotKeyManager::new().map_err(|e| HotkeyError::Register {
+        hotkey: bindings[0].0.clone(),
+        message: format!("failed to create hotkey manager: {}", e),
+    })?;
 
-    if let Err(e) = manager.register(hotkey) {
-        eprintln!("Warning: failed to register hotkey '{}': {}. Hotkey disabled, badge click still works.", configured_hotkey, e);
-        return;
+    let mut registered = Vec::new();
+    for (chord, hotkey, _) in &parsed {
+        if let Err(e) = manager.register(*hotkey) {
+            let _ = manager.unregister_all(&registered);
+            return Err(HotkeyError::Register { hotkey: chord.clone(), message: e.to_string() });
+        }
+        registered.push(*hotkey);
     }
 
-    struct SendManager(GlobalHotKeyManager);
-    unsafe impl Send for SendManager {}
-
-    let manager = SendManager(manager);
+    let actions: HashMap<u32, HotkeyAction> = parsed.iter().map(|(_, hotkey, action)| (hotkey.id(), *action)).collect();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_clone = stop.clone();
 
+    // Poll with a timeout so a replaced listener notices the stop flag instead
+    // of competing with its successor for events on the global receiver.
     std::thread::spawn(move || {
-        let _manager = manager;
-        loop {
-            if let Ok(_event) = GlobalHotKeyEvent::receiver().recv() {
-                let _ = tx.blocking_send(AppEvent::ToggleRecording);
+        while !stop_clone.load(Ordering::SeqCst) {
+            let event = match GlobalHotKeyEvent::receiver().recv_timeout(std::time::Duration::from_millis(100)) {
+                Ok(event) => event,
+                Err(_) => continue,
+            };
+            let Some(action) = actions.get(&event.id).copied() else {
+                continue;
+            };
+            match event.state {
+                HotKeyState::Pressed => {
+                    let _ = tx.blocking_send(press_event(action, mode));
+                }
+                HotKeyState::Released => {
+                    if let Some(event) = release_event(action, mode) {
+                        let _ = tx.blocking_send(event);
+                    }
+                }
             }
         }
     });
+
+    Ok(HotkeyListener {
+        stop,
+        registration: Some((SendManager(manager), registered)),
+    })
 }
 
 #[cfg(target_os = "windows")]
@@ -290,3 +454,86 @@ fn key_name_to_code(name: &str) -> Option<global_hotkey::hotkey::Code> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(chords: &[(&str, HotkeyAction)]) -> Vec<(String, HotkeyAction)> {
+        chords.iter().map(|(chord, action)| (chord.to_string(), *action)).collect()
+    }
+
+    #[test]
+    fn active_bindings_skip_disabled_chords() {
+        let all = bindings(&[
+            (" Ctrl+Shift+R ", HotkeyAction::Toggle),
+            ("None", HotkeyAction::Cancel),
+            ("", HotkeyAction::RecopyLast),
+            ("none", HotkeyAction::OpenStats),
+        ]);
+        assert_eq!(active_bindings(&all), bindings(&[("Ctrl+Shift+R", HotkeyAction::Toggle)]));
+    }
+
+    #[test]
+    fn press_and_release_events_follow_the_mode() {
+        assert!(matches!(press_event(HotkeyAction::Toggle, HotkeyMode::Toggle), AppEvent::ToggleRecording));
+        assert!(matches!(press_event(HotkeyAction::Toggle, HotkeyMode::Hold), AppEvent::StartRecording));
+        assert!(matches!(release_event(HotkeyAction::Toggle, HotkeyMode::Hold), Some(AppEvent::StopRecording)));
+        assert!(release_event(HotkeyAction::Toggle, HotkeyMode::Toggle).is_none());
+
+        // Other actions fire once on press, whatever the mode.
+        for mode in [HotkeyMode::Toggle, HotkeyMode::Hold] {
+            assert!(matches!(press_event(HotkeyAction::Cancel, mode), AppEvent::Cancel));
+            assert!(matches!(press_event(HotkeyAction::RecopyLast, mode), AppEvent::RecopyLast));
+            assert!(matches!(press_event(HotkeyAction::ToggleAppend, mode), AppEvent::ToggleAppendMode));
+            assert!(matches!(press_event(HotkeyAction::OpenStats, mode), AppEvent::OpenStats));
+            for action in [HotkeyAction::Cancel, HotkeyAction::RecopyLast, HotkeyAction::ToggleAppend, HotkeyAction::OpenStats] {
+                assert!(release_event(action, mode).is_none());
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_evdev_chords() {
+        use evdev::KeyCode;
+        let parsed = parse_hotkey_evdev("ctrl + Shift + r").unwrap();
+        assert_eq!(parsed.trigger, KeyCode::KEY_R);
+        assert_eq!(
+            parsed.modifiers,
+            [(KeyCode::KEY_LEFTCTRL, KeyCode::KEY_RIGHTCTRL), (KeyCode::KEY_LEFTSHIFT, KeyCode::KEY_RIGHTSHIFT)]
+        );
+        assert_eq!(parse_hotkey_evdev("F9").unwrap().modifiers, []);
+        assert_eq!(parse_hotkey_evdev("Super+Alt+1").unwrap().trigger, KeyCode::KEY_1);
+        assert_eq!(parse_hotkey_evdev("Win+A"), parse_hotkey_evdev("meta+a"));
+        assert_eq!(parse_hotkey_evdev("Control+Shift+A"), parse_hotkey_evdev("Shift+Ctrl+A"));
+
+        for invalid in ["", "Ctrl+", "Hyper+A", "Ctrl+Shift", "Ctrl+NotAKey"] {
+            assert!(parse_hotkey_evdev(invalid).is_none(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn validation_rejects_unparseable_and_duplicate_chords() {
+        assert!(validate_bindings(&bindings(&[
+            ("Ctrl+Shift+R", HotkeyAction::Toggle),
+            ("Ctrl+Shift+C", HotkeyAction::Cancel),
+            ("None", HotkeyAction::RecopyLast),
+            ("None", HotkeyAction::OpenStats),
+        ]))
+        .is_ok());
+
+        let error = validate_bindings(&bindings(&[("Ctrl+Bogus", HotkeyAction::Toggle)])).unwrap_err();
+        assert!(matches!(error, HotkeyError::Parse { hotkey } if hotkey == "Ctrl+Bogus"));
+
+        let error = validate_bindings(&bindings(&[
+            ("Ctrl+Shift+R", HotkeyAction::Toggle),
+            ("shift+control+r", HotkeyAction::Cancel),
+        ]))
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            HotkeyError::Duplicate { hotkey, other } if hotkey == "shift+control+r" && other == "Ctrl+Shift+R"
+        ));
+    }
+}
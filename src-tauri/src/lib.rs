@@ -1,22 +1,44 @@
 mod config;
+mod archive;
 mod audio;
 mod whisper;
 mod delivery;
 mod db;
+mod filter;
+mod flac;
 mod hotkey;
+mod models;
+mod postprocess;
+mod snippets;
+mod tempfiles;
+mod transcript;
+mod transcription;
 
 use config::Config;
 use std::sync::Mutex;
 use tauri::{AppHandle, Emitter, Manager};
+use transcription::{
+    archive_audio, enforce_audio_retention, keep_audio, log_session, schedule_idle, transcribe_and_deliver,
+    TranscriptionJob, TranscriptionRequest, TranscriptionSetup,
+};
 
 #[derive(Debug)]
 pub enum AppEvent {
     ToggleRecording,
+    StartRecording,
+    StopRecording,
+    SilenceDetected,
+    MaxDurationReached,
+    Cancel,
+    RecopyLast,
+    ToggleAppendMode,
+    OpenStats,
 }
 
 enum AudioCommand {
-    Start(Option<String>),
-    StopAndSave(String, tokio::sync::oneshot::Sender<anyhow::Result<()>>),
+    Start(Option<String>, Option<audio::VadSettings>),
+    StopAndSave(std::path::PathBuf, tokio::sync::oneshot::Sender<anyhow::Result<()>>),
+    Discard,
     GetLevel(tokio::sync::oneshot::Sender<f32>),
 }
 
@@ -24,6 +46,40 @@ struct AppState {
     config: Mutex<Config>,
     db: Mutex<db::Database>,
     event_tx: tokio::sync::mpsc::Sender<AppEvent>,
+    hotkey_listener: Mutex<Option<hotkey::HotkeyListener>>,
+    transcriber: Mutex<std::sync::Arc<dyn whisper::Transcriber>>,
+}
+
+/// Error returned to the settings window by `save_config`.
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SaveConfigError {
+    InvalidHotkey { hotkey: String, message: String },
+    HotkeyRegistration { hotkey: String, message: String },
+    DuplicateHotkey { hotkey: String, message: String },
+    InvalidWhisperOptions { message: String },
+    InvalidFilter { message: String },
+    InvalidTimeout { message: String },
+    Save { message: String },
+}
+
+impl From<hotkey::HotkeyError> for SaveConfigError {
+    fn from(e: hotkey::HotkeyError) -> Self {
+        let message = e.to_string();
+        match e {
+            hotkey::HotkeyError::Parse { hotkey } => SaveConfigError::InvalidHotkey { hotkey, message },
+            hotkey::HotkeyError::Register { hotkey, message: _ } => {
+                SaveConfigError::HotkeyRegistration { hotkey, message }
+            }
+            hotkey::HotkeyError::Duplicate { hotkey, other: _ } => SaveConfigError::DuplicateHotkey { hotkey, message },
+        }
+    }
+}
+
+impl SaveConfigError {
+    fn save(e: impl std::fmt::Display) -> Self {
+        SaveConfigError::Save { message: e.to_string() }
+    }
 }
 
 #[tauri::command]
@@ -32,6 +88,12 @@ async fn toggle_recording(state: tauri::State<'_, AppState>) -> Result<(), Strin
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn cancel_recording(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.event_tx.send(AppEvent::Cancel).await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn get_config(state: tauri::State<'_, AppState>) -> Result<Config, String> {
     let config = state.config.lock().map_err(|e| e.to_string())?;
@@ -39,13 +101,90 @@ fn get_config(state: tauri::State<'_, AppState>) -> Result<Config, String> {
 }
 
 #[tauri::command]
-fn save_config(state: tauri::State<'_, AppState>, new_config: Config) -> Result<(), String> {
-    new_config.save().map_err(|e| e.to_string())?;
-    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+fn save_config(state: tauri::State<'_, AppState>, new_config: Config) -> Result<(), SaveConfigError> {
+    let (old_bindings, old_mode) = {
+        let config = state.config.lock().map_err(SaveConfigError::save)?;
+        (config.hotkey_bindings(), config.hotkey_mode)
+    };
+    let new_bindings = new_config.hotkey_bindings();
+
+    // The model may not be downloaded yet; then validation falls back to its name.
+    let multilingual = Config::get_models_dir()
+        .ok()
+        .and_then(|dir| models::read_info(&models::resolve(&dir, &new_config.model_name)).ok())
+        .map(|info| info.multilingual);
+    whisper::validate_options(&new_config.whisper, &new_config.model_name, multilingual)
+        .map_err(|e| SaveConfigError::InvalidWhisperOptions { message: e.to_string() })?;
+    filter::TranscriptFilter::new(&new_config.filter)
+        .map_err(|e| SaveConfigError::InvalidFilter { message: format!("{:#}", e) })?;
+    new_config.validate_timeout().map_err(|e| SaveConfigError::InvalidTimeout { message: e.to_string() })?;
+
+    let rebind = new_bindings != old_bindings || new_config.hotkey_mode != old_mode;
+    if rebind {
+        hotkey::validate_bindings(&new_bindings)?;
+        rebind_hotkeys(&state, &new_bindings, new_config.hotkey_mode, &old_bindings, old_mode)?;
+    }
+
+    if let Err(e) = new_config.save() {
+        // The old config stays in effect, so its chords must be the live ones.
+        if rebind {
+            let _ = rebind_hotkeys(&state, &old_bindings, old_mode, &old_bindings, old_mode);
+        }
+        return Err(SaveConfigError::save(e));
+    }
+    let mut config = state.config.lock().map_err(SaveConfigError::save)?;
+    if new_config.transcriber != config.transcriber || new_config.whisper_server_port != config.whisper_server_port {
+        let mut transcriber = state.transcriber.lock().map_err(SaveConfigError::save)?;
+        transcriber.shutdown();
+        *transcriber = whisper::create_transcriber(&new_config);
+    }
     *config = new_config;
     Ok(())
 }
 
+/// Replaces the hotkey listener with one for `bindings`. If they cannot be
+/// bound, `fallback` is bound again and the error returned.
+fn rebind_hotkeys(
+    state: &AppState,
+    bindings: &[(String, config::HotkeyAction)],
+    mode: config::HotkeyMode,
+    fallback: &[(String, config::HotkeyAction)],
+    fallback_mode: config::HotkeyMode,
+) -> Result<(), SaveConfigError> {
+    // Release the old bindings first so re-registering the same chord works.
+    let mut listener = state.hotkey_listener.lock().map_err(SaveConfigError::save)?;
+    listener.take();
+    match hotkey::start_hotkey_listener(state.event_tx.clone(), bindings, mode) {
+        Ok(l) => *listener = Some(l),
+        Err(e) => {
+            *listener = hotkey::start_hotkey_listener(state.event_tx.clone(), fallback, fallback_mode).ok();
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn list_models() -> Result<Vec<models::ModelInfo>, String> {
+    let dir = Config::get_models_dir().map_err(|e| e.to_string())?;
+    models::list_models(&dir).map_err(|e| e.to_string())
+}
+
+/// Switches to `name` after checking it is a readable model that supports the
+/// configured language options, and saves the config.
+#[tauri::command]
+fn set_model(state: tauri::State<'_, AppState>, name: String) -> Result<models::ModelInfo, String> {
+    let dir = Config::get_models_dir().map_err(|e| e.to_string())?;
+    let info = models::validate(&dir, &name).map_err(|e| format!("{:#}", e))?;
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    whisper::validate_options(&config.whisper, &name, Some(info.multilingual)).map_err(|e| e.to_string())?;
+    let mut new_config = config.clone();
+    new_config.model_name = name;
+    new_config.save().map_err(|e| e.to_string())?;
+    *config = new_config;
+    Ok(info)
+}
+
 #[tauri::command]
 fn list_input_devices() -> Result<Vec<String>, String> {
     audio::list_input_devices().map_err(|e| e.to_string())
@@ -63,6 +202,310 @@ fn get_recent_sessions(state: tauri::State<'_, AppState>, limit: u32) -> Result<
     db.get_recent_sessions(limit).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn get_session_segments(state: tauri::State<'_, AppState>, session_id: i64) -> Result<Vec<db::SegmentRow>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_session_segments(session_id).map_err(|e| e.to_string())
+}
+
+/// Transcribes a failed session's kept recording again, optionally with a
+/// different model, and updates the session in place.
+#[tauri::command]
+async fn retry_session(app_handle: AppHandle, session_id: i64, model: Option<String>) -> Result<db::SessionRow, String> {
+    let session = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_session(session_id).map_err(|e| e.to_string())?
+    }.ok_or("Session not found")?;
+    let recording_path = std::path::PathBuf::from(
+        session.failed_audio_path.ok_or("This session has no kept recording to retry")?
+    );
+    if !recording_path.exists() {
+        return Err(format!("Recording {:?} no longer exists", recording_path));
+    }
+
+    let model = model.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    let setup = TranscriptionSetup::from_config(&app_handle, recording_path.clone(), session.duration_secs, model);
+    let mut record = setup.session_record(session.started_at, session.duration_secs, session.truncated);
+    let start_time = std::time::Instant::now();
+    let result = setup.run().await;
+    record.latency_ms = start_time.elapsed().as_millis() as u32;
+
+    let record = match result {
+        Ok(output) => {
+            if keep_audio(&app_handle) {
+                record.audio_path = archive_audio(&recording_path).await;
+            }
+            output.into_record(record)
+        }
+        Err(e) => {
+            eprintln!("Retry of session {} failed: {:?}", session_id, e);
+            db::SessionRecord {
+                failed_audio_path: Some(recording_path.to_string_lossy().to_string()),
+                ..transcription::failure_record(&e, record)
+            }
+        }
+    };
+    let succeeded = record.error.is_none();
+    let archived = record.audio_path.is_some();
+
+    let state = app_handle.state::<AppState>();
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.update_session(session_id, record).map_err(|e| e.to_string())?;
+    if succeeded {
+        if let Err(e) = std::fs::remove_file(&recording_path) {
+            eprintln!("Failed to delete {:?}: {}", recording_path, e);
+        }
+    }
+    if archived {
+        enforce_audio_retention(&app_handle, &db);
+    }
+    db.get_session(session_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Session not found".to_string())
+}
+
+/// Transcribes a session's kept recording with `model` and stores the result
+/// as an alternate transcript, leaving the session itself untouched.
+#[tauri::command]
+async fn retranscribe(app_handle: AppHandle, session_id: i64, model: String) -> Result<db::TranscriptRow, String> {
+    let session = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_session(session_id).map_err(|e| e.to_string())?
+    }.ok_or("Session not found")?;
+    let model = model.trim().to_string();
+    if model.is_empty() {
+        return Err("Choose a model to re-transcribe with".to_string());
+    }
+
+    // whisper needs a WAV file; archived audio is FLAC and is decoded to a temp file.
+    let restored = tempfiles::TempRecording::create().map_err(|e| e.to_string())?;
+    let wav_path = match (session.audio_path, session.failed_audio_path) {
+        (Some(flac_path), _) => {
+            let dest = restored.path().to_path_buf();
+            tokio::task::spawn_blocking(move || archive::restore_to_wav(std::path::Path::new(&flac_path), &dest))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| format!("{:#}", e))?;
+            restored.path().to_path_buf()
+        }
+        (None, Some(wav_path)) => std::path::PathBuf::from(wav_path),
+        (None, None) => return Err("No audio was kept for this session".to_string()),
+    };
+
+    let setup = TranscriptionSetup::from_config(&app_handle, wav_path, session.duration_secs, Some(model));
+    let start_time = std::time::Instant::now();
+    let output = setup.run().await.map_err(|e| format!("{:#}", e))?;
+    drop(restored);
+
+    let state = app_handle.state::<AppState>();
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.add_transcript(db::TranscriptRecord {
+        session_id,
+        model_used: output.model_used,
+        latency_ms: start_time.elapsed().as_millis() as u32,
+        whisper_options: setup.whisper_options(),
+        transcript: transcript::Transcript { text: output.text, ..output.filtered },
+    }).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_transcripts(state: tauri::State<'_, AppState>, session_id: i64) -> Result<Vec<db::TranscriptRow>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_transcripts(session_id).map_err(|e| e.to_string())
+}
+
+/// Bytes of a session's recording for playback: the FLAC archive if there is
+/// one, otherwise the WAV kept after a failed transcription.
+#[tauri::command]
+fn get_session_audio(state: tauri::State<'_, AppState>, session_id: i64) -> Result<tauri::ipc::Response, String> {
+    let session = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_session(session_id).map_err(|e| e.to_string())?
+    }.ok_or("Session not found")?;
+    let path = session.audio_path.or(session.failed_audio_path)
+        .ok_or("No audio was kept for this session")?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+#[tauri::command]
+fn list_vocabulary(state: tauri::State<'_, AppState>) -> Result<Vec<db::VocabularyTerm>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_vocabulary().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_vocabulary_term(state: tauri::State<'_, AppState>, term: String, language: Option<String>) -> Result<i64, String> {
+    let term = term.trim();
+    if term.is_empty() {
+        return Err("Vocabulary term cannot be empty".to_string());
+    }
+    let language = language
+        .map(|l| l.trim().to_ascii_lowercase())
+        .filter(|l| !l.is_empty() && l != "auto")
+        .unwrap_or_else(|| "*".to_string());
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.add_vocabulary_term(term, &language).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn remove_vocabulary_term(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.remove_vocabulary_term(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn list_rules(state: tauri::State<'_, AppState>) -> Result<Vec<db::ReplacementRule>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_rules().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_rule(state: tauri::State<'_, AppState>, rule: db::ReplacementRule) -> Result<db::ReplacementRule, String> {
+    postprocess::CompiledRule::new(&rule).map_err(|e| format!("{:#}", e))?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let id = db.add_rule(&rule).map_err(|e| e.to_string())?;
+    Ok(db::ReplacementRule { id, ..rule })
+}
+
+#[tauri::command]
+fn update_rule(state: tauri::State<'_, AppState>, rule: db::ReplacementRule) -> Result<(), String> {
+    postprocess::CompiledRule::new(&rule).map_err(|e| format!("{:#}", e))?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.update_rule(&rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn remove_rule(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.remove_rule(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn list_snippets(state: tauri::State<'_, AppState>) -> Result<Vec<db::Snippet>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.list_snippets().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_snippet(state: tauri::State<'_, AppState>, trigger: String, body: String) -> Result<db::Snippet, String> {
+    let trigger = trigger.trim().to_string();
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    check_snippet_trigger(&db, &trigger, None)?;
+    let id = db.add_snippet(&trigger, &body).map_err(|e| format!("{:#}", e))?;
+    Ok(db::Snippet { id, trigger, body })
+}
+
+#[tauri::command]
+fn update_snippet(state: tauri::State<'_, AppState>, snippet: db::Snippet) -> Result<(), String> {
+    let snippet = db::Snippet { trigger: snippet.trigger.trim().to_string(), ..snippet };
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    check_snippet_trigger(&db, &snippet.trigger, Some(snippet.id))?;
+    db.update_snippet(&snippet).map_err(|e| format!("{:#}", e))
+}
+
+/// Triggers are matched by their words, so "sign-off" and "Sign Off" would
+/// collide even though the database sees two different strings.
+fn check_snippet_trigger(db: &db::Database, trigger: &str, id: Option<i64>) -> Result<(), String> {
+    if snippets::normalize_trigger(trigger).is_empty() {
+        return Err("Snippet trigger cannot be empty".to_string());
+    }
+    let existing = db.list_snippets().map_err(|e| e.to_string())?;
+    match snippets::conflicting(trigger, id, &existing) {
+        Some(other) => Err(format!("Trigger '{}' is spoken the same as the existing snippet '{}'", trigger, other.trigger)),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+fn remove_snippet(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.remove_snippet(id).map_err(|e| e.to_string())
+}
+
+/// A recent session whose text `preview_rule` would change.
+#[derive(Debug, Clone, serde::Serialize)]
+struct RulePreview {
+    session_id: i64,
+    started_at: i64,
+    before: String,
+    after: String,
+}
+
+/// Runs whisper's raw text of the last 50 transcriptions through the noise
+/// filter and post-processing, once with the saved rules and once with `rule`
+/// added (or replacing the saved rule with its id), and returns the sessions
+/// whose text would change. Nothing is saved, and snippets are not expanded.
+#[tauri::command]
+fn preview_rule(state: tauri::State<'_, AppState>, rule: db::ReplacementRule) -> Result<Vec<RulePreview>, String> {
+    postprocess::CompiledRule::new(&rule).map_err(|e| format!("{:#}", e))?;
+    let (rules, sessions) = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        (
+            db.list_rules().map_err(|e| e.to_string())?,
+            db.get_recent_transcriptions(50).map_err(|e| e.to_string())?,
+        )
+    };
+    let (filter_options, options) = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        (config.filter.clone(), config.postprocess.clone())
+    };
+    let filter = filter::TranscriptFilter::new(&filter_options).map_err(|e| format!("{:#}", e))?;
+    rule_previews(&filter, &options, &rules, rule, sessions).map_err(|e| format!("{:#}", e))
+}
+
+fn rule_previews(
+    filter: &filter::TranscriptFilter,
+    options: &config::PostProcessOptions,
+    rules: &[db::ReplacementRule],
+    candidate: db::ReplacementRule,
+    sessions: Vec<db::SessionRow>,
+) -> anyhow::Result<Vec<RulePreview>> {
+    let current = postprocess::PostProcessor::new(options, rules)?;
+    let candidate = db::ReplacementRule { enabled: true, ..candidate };
+    let mut with_candidate = rules.to_vec();
+    match with_candidate.iter_mut().find(|r| candidate.id != 0 && r.id == candidate.id) {
+        Some(saved) => *saved = candidate,
+        None => with_candidate.push(candidate),
+    }
+    let proposed = postprocess::PostProcessor::new(options, &with_candidate)?;
+
+    Ok(sessions
+        .into_iter()
+        .filter_map(|s| {
+            // Only the text is stored, so the filter sees it as a single segment.
+            let filtered = filter.apply(&transcript::Transcript::from_text(s.raw_transcription.as_deref()?));
+            if filtered.text.is_empty() {
+                return None;
+            }
+            let language = preview_language(&s);
+            let before = current.apply(&filtered.text, &language);
+            let after = proposed.apply(&filtered.text, &language);
+            (before != after).then_some(RulePreview { session_id: s.id, started_at: s.started_at, before, after })
+        })
+        .collect())
+}
+
+/// The language `session`'s text was post-processed with.
+fn preview_language(session: &db::SessionRow) -> String {
+    let options = session
+        .whisper_options
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<config::WhisperOptions>(json).ok());
+    if options.as_ref().is_some_and(|o| o.translate) {
+        return "en".to_string();
+    }
+    if let Some(language) = &session.language {
+        return language.clone();
+    }
+    match options.map(|o| o.language.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("auto") => "en".to_string(),
+        Some(language) => language.to_string(),
+    }
+}
+
 #[tauri::command]
 async fn open_settings_window(app: AppHandle) -> Result<(), String> {
     if let Some(win) = app.get_webview_window("settings") {
@@ -102,29 +545,303 @@ fn save_badge_position(state: tauri::State<'_, AppState>, x: i32, y: i32) -> Res
     Ok(())
 }
 
+/// Seconds before `max_recording_duration` at which `recording-warning` events start.
+const MAX_DURATION_WARNING_SECS: u32 = 10;
+
+struct Orchestrator {
+    app_handle: AppHandle,
+    audio_tx: std::sync::mpsc::Sender<AudioCommand>,
+    event_tx: tokio::sync::mpsc::Sender<AppEvent>,
+    is_recording: bool,
+    recording_start_time: i64,
+    level_poll_task: Option<tokio::task::JoinHandle<()>>,
+    duration_limit_task: Option<tokio::task::JoinHandle<()>>,
+    transcription: Option<TranscriptionJob>,
+}
+
+impl Orchestrator {
+    fn new(
+        app_handle: AppHandle,
+        audio_tx: std::sync::mpsc::Sender<AudioCommand>,
+        event_tx: tokio::sync::mpsc::Sender<AppEvent>,
+    ) -> Self {
+        Self {
+            app_handle,
+            audio_tx,
+            event_tx,
+            is_recording: false,
+            recording_start_time: 0,
+            level_poll_task: None,
+            duration_limit_task: None,
+            transcription: None,
+        }
+    }
+
+    async fn handle_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::ToggleRecording => {
+                if !self.is_recording {
+                    self.start_recording();
+                } else {
+                    self.stop_and_transcribe(false).await;
+                }
+            }
+            AppEvent::StartRecording => {
+                if !self.is_recording {
+                    self.start_recording();
+                }
+            }
+            AppEvent::StopRecording => {
+                if self.is_recording {
+                    self.stop_and_transcribe(false).await;
+                }
+            }
+            AppEvent::SilenceDetected => {
+                if self.is_recording {
+                    println!("Silence detected, stopping recording");
+                    self.stop_and_transcribe(false).await;
+                }
+            }
+            AppEvent::MaxDurationReached => {
+                if self.is_recording {
+                    println!("Maximum recording duration reached, stopping recording");
+                    self.stop_and_transcribe(true).await;
+                }
+            }
+            AppEvent::Cancel => {
+                if self.is_recording {
+                    self.cancel_recording();
+                } else {
+                    self.cancel_transcription();
+                }
+            }
+            AppEvent::RecopyLast => self.recopy_last().await,
+            AppEvent::ToggleAppendMode => self.toggle_append_mode().await,
+            AppEvent::OpenStats => {
+                if let Err(e) = open_stats_window(self.app_handle.clone()).await {
+                    eprintln!("Failed to open stats window: {}", e);
+                }
+            }
+        }
+    }
+
+    fn stop_timers(&mut self) {
+        if let Some(task) = self.level_poll_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.duration_limit_task.take() {
+            task.abort();
+        }
+    }
+
+    fn cancel_recording(&mut self) {
+        self.is_recording = false;
+        self.stop_timers();
+        let _ = self.audio_tx.send(AudioCommand::Discard);
+        let duration_secs = (db::current_timestamp() - self.recording_start_time) as u32;
+        log_session(&self.app_handle, db::SessionRecord {
+            cancelled: true,
+            ..db::SessionRecord::new(self.recording_start_time, duration_secs, false)
+        });
+        let _ = self.app_handle.emit("badge-state", "idle");
+        println!("Recording cancelled");
+    }
+
+    async fn recopy_last(&self) {
+        let last = {
+            let state = self.app_handle.state::<AppState>();
+            let db = state.db.lock().unwrap();
+            db.get_last_transcription()
+        };
+        match last {
+            Ok(Some(text)) => {
+                let res = tokio::task::spawn_blocking(move || {
+                    delivery::copy_to_clipboard(&text, false)?;
+                    delivery::notify("VoiceClip", "Copied last transcription", false)
+                }).await.unwrap_or_else(|e| Err(anyhow::anyhow!(e)));
+                if let Err(e) = res {
+                    eprintln!("Re-copy failed: {:?}", e);
+                }
+            }
+            Ok(None) => {
+                let _ = tokio::task::spawn_blocking(|| {
+                    delivery::notify("VoiceClip", "No transcription to copy yet", false)
+                }).await;
+            }
+            Err(e) => eprintln!("Failed to load last transcription: {:?}", e),
+        }
+    }
+
+    async fn toggle_append_mode(&self) {
+        let res = {
+            let state = self.app_handle.state::<AppState>();
+            let mut cfg = state.config.lock().unwrap();
+            cfg.append_mode = !cfg.append_mode;
+            cfg.save().map(|_| cfg.append_mode)
+        };
+        match res {
+            Ok(append) => {
+                let body = if append { "Append mode on" } else { "Append mode off" };
+                let _ = tokio::task::spawn_blocking(move || delivery::notify("VoiceClip", body, false)).await;
+            }
+            Err(e) => eprintln!("Failed to save append mode: {:?}", e),
+        }
+    }
+
+    fn start_recording(&mut self) {
+        if self.is_transcribing() {
+            println!("Still transcribing the previous recording, ignoring start");
+            return;
+        }
+        let (mic, vad, max_secs) = {
+            let state = self.app_handle.state::<AppState>();
+            let cfg = state.config.lock().unwrap();
+            let vad = cfg.vad_enabled.then_some(audio::VadSettings {
+                threshold: cfg.vad_threshold,
+                silence_ms: cfg.vad_silence_ms,
+            });
+            (cfg.microphone.clone(), vad, cfg.max_recording_duration)
+        };
+        let _ = self.audio_tx.send(AudioCommand::Start(mic, vad));
+        self.is_recording = true;
+        self.recording_start_time = db::current_timestamp();
+        let _ = self.app_handle.emit("badge-state", "recording");
+        println!("Recording started");
+
+        // Start audio level polling
+        let handle = self.app_handle.clone();
+        let atx = self.audio_tx.clone();
+        self.level_poll_task = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_millis(33)).await;
+                let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+                if atx.send(AudioCommand::GetLevel(reply_tx)).is_err() {
+                    break;
+                }
+                if let Ok(level) = reply_rx.await {
+                    let _ = handle.emit("audio-level", level);
+                }
+            }
+        }));
+
+        // Enforce max_recording_duration (0 disables the limit)
+        if max_secs > 0 {
+            let handle = self.app_handle.clone();
+            let tx = self.event_tx.clone();
+            self.duration_limit_task = Some(tokio::spawn(async move {
+                let warn_at = max_secs.saturating_sub(MAX_DURATION_WARNING_SECS);
+                tokio::time::sleep(tokio::time::Duration::from_secs(warn_at as u64)).await;
+                for remaining in (1..=max_secs - warn_at).rev() {
+                    let _ = handle.emit("recording-warning", remaining);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                }
+                let _ = tx.send(AppEvent::MaxDurationReached).await;
+            }));
+        }
+    }
+
+    async fn stop_and_transcribe(&mut self, truncated: bool) {
+        self.is_recording = false;
+        self.stop_timers();
+        let _ = self.app_handle.emit("badge-state", "processing");
+
+        let recording = match tempfiles::TempRecording::create() {
+            Ok(recording) => recording,
+            Err(e) => {
+                eprintln!("Failed to create temporary recording: {:?}", e);
+                let _ = self.audio_tx.send(AudioCommand::Discard);
+                let _ = self.app_handle.emit("badge-state", "error");
+                schedule_idle(&self.app_handle);
+                return;
+            }
+        };
+
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        let _ = self.audio_tx.send(AudioCommand::StopAndSave(recording.path().to_path_buf(), reply_tx));
+
+        let stop_res = reply_rx.await.unwrap_or_else(|_| Err(anyhow::anyhow!("Audio thread died")));
+
+        match stop_res {
+            Ok(()) => {
+                let duration_secs = (db::current_timestamp() - self.recording_start_time) as u32;
+                let setup = TranscriptionSetup::from_config(
+                    &self.app_handle,
+                    recording.path().to_path_buf(),
+                    duration_secs,
+                    None,
+                );
+                let append = {
+                    let state = self.app_handle.state::<AppState>();
+                    let cfg = state.config.lock().unwrap();
+                    cfg.append_mode
+                };
+                let (cancel, cancelled) = tokio::sync::oneshot::channel();
+                self.transcription = Some(TranscriptionJob {
+                    cancel: Some(cancel),
+                    task: tokio::spawn(transcribe_and_deliver(self.app_handle.clone(), TranscriptionRequest {
+                        setup,
+                        recording,
+                        append,
+                        started_at: self.recording_start_time,
+                        duration_secs,
+                        truncated,
+                        cancelled,
+                    })),
+                });
+            }
+            Err(_) => {
+                eprintln!("Failed to save recording.");
+                let _ = self.app_handle.emit("badge-state", "error");
+                schedule_idle(&self.app_handle);
+            }
+        }
+    }
+
+    fn is_transcribing(&self) -> bool {
+        self.transcription.as_ref().is_some_and(|job| !job.task.is_finished())
+    }
+
+    /// Only stops the transcription itself. Once it has finished, delivery
+    /// and logging go ahead, so the request is ignored.
+    fn cancel_transcription(&mut self) {
+        if let Some(cancel) = self.transcription.as_mut().and_then(|job| job.cancel.take()) {
+            let _ = cancel.send(());
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_process::init())
         .setup(|app| {
             let config = Config::load().expect("Failed to load config");
+            // Nothing is recording yet; recordings of another running instance are kept.
+            tempfiles::remove_stale();
             let db_path = Config::get_db_path().expect("Failed to get DB path");
             let database = db::Database::new(&db_path).expect("Failed to init database");
+            if let Err(e) = archive::enforce_retention(&database, config.audio_retention_days, config.audio_retention_max_mb) {
+                eprintln!("Failed to apply audio retention: {:?}", e);
+            }
 
             println!("VoiceClip initializing...");
             println!("Loaded configuration: {:#?}", config);
             println!("Database initialized at {:?}", db_path);
 
+            // Event channel for orchestrator
+            let (event_tx, mut event_rx) = tokio::sync::mpsc::channel::<AppEvent>(32);
+
             // Audio worker thread
             let (audio_tx, audio_rx) = std::sync::mpsc::channel::<AudioCommand>();
 
+            let tx_audio = event_tx.clone();
             std::thread::spawn(move || {
                 let mut recorder: Option<audio::AudioRecorder> = None;
                 while let Ok(cmd) = audio_rx.recv() {
                     match cmd {
-                        AudioCommand::Start(device_name) => {
+                        AudioCommand::Start(device_name, vad) => {
                             if recorder.is_none() {
-                                match audio::AudioRecorder::start_recording(device_name.as_deref()) {
+                                match audio::AudioRecorder::start_recording(device_name.as_deref(), vad, tx_audio.clone()) {
                                     Ok(r) => recorder = Some(r),
                                     Err(e) => eprintln!("Audio recording failed to start: {}", e),
                                 }
@@ -138,9 +855,14 @@ pub fn run() {
                                 let _ = reply.send(Err(anyhow::anyhow!("Not recording")));
                             }
                         }
+                        AudioCommand::Discard => {
+                            if let Some(r) = recorder.take() {
+                                r.discard();
+                            }
+                        }
                         AudioCommand::GetLevel(reply) => {
                             if let Some(ref r) = recorder {
-                                let _ = reply.send(r.drain_levels());
+                                let _ = reply.send(r.current_level());
                             } else {
                                 let _ = reply.send(0.0);
                             }
@@ -149,12 +871,30 @@ pub fn run() {
                 }
             });
 
-            // Event channel for orchestrator
-            let (event_tx, mut event_rx) = tokio::sync::mpsc::channel::<AppEvent>(32);
-
             // Start hotkey listener
             let tx_hotkey = event_tx.clone();
-            hotkey::start_hotkey_listener(tx_hotkey, &config.hotkey);
+            let hotkey_listener = match hotkey::start_hotkey_listener(tx_hotkey, &config.hotkey_bindings(), config.hotkey_mode) {
+                Ok(l) => Some(l),
+                Err(e) => {
+                    eprintln!("Warning: {}. Hotkey disabled, badge click still works.", e);
+                    None
+                }
+            };
+
+            if config.transcriber != config::TranscriberBackend::Mock {
+                let model = Config::get_models_dir().and_then(|dir| models::validate(&dir, &config.model_name));
+                match model {
+                    Ok(info) => println!("Model: {:?}", info),
+                    Err(e) => {
+                        let message = format!("{:#}", e);
+                        eprintln!("Warning: {}", message);
+                        std::thread::spawn(move || delivery::notify("VoiceClip Model Missing", &message, true));
+                    }
+                }
+            }
+
+            let transcriber = whisper::create_transcriber(&config);
+            println!("Transcriber: {:?}", transcriber.capabilities());
 
             // Restore badge position
             if let Some(badge_win) = app.get_webview_window("badge") {
@@ -168,175 +908,21 @@ pub fn run() {
 
             // Store shared state
             let state = AppState {
-                config: Mutex::new(config.clone()),
+                config: Mutex::new(config),
                 db: Mutex::new(database),
-                event_tx,
+                event_tx: event_tx.clone(),
+                hotkey_listener: Mutex::new(hotkey_listener),
+                transcriber: Mutex::new(transcriber),
             };
             app.manage(state);
 
             // Spawn orchestrator
-            let app_handle = app.handle().clone();
-            let config_for_orchestrator = config;
-            let audio_tx_orch = audio_tx;
+            let mut orchestrator = Orchestrator::new(app.handle().clone(), audio_tx, event_tx.clone());
 
             tokio::spawn(async move {
-                let mut is_recording = false;
-                let mut recording_start_time: i64 = 0;
-                let mut level_poll_task: Option<tokio::task::JoinHandle<()>> = None;
-
                 while let Some(event) = event_rx.recv().await {
                     println!("Received AppEvent: {:?}", event);
-                    match event {
-                        AppEvent::ToggleRecording => {
-                            if !is_recording {
-                                // Start recording
-                                let mic = {
-                                    let state = app_handle.state::<AppState>();
-                                    let cfg = state.config.lock().unwrap();
-                                    cfg.microphone.clone()
-                                };
-                                let _ = audio_tx_orch.send(AudioCommand::Start(mic));
-                                is_recording = true;
-                                recording_start_time = db::current_timestamp();
-                                let _ = app_handle.emit("badge-state", "recording");
-                                println!("Recording started");
-
-                                // Start audio level polling
-                                let handle = app_handle.clone();
-                                let atx = audio_tx_orch.clone();
-                                level_poll_task = Some(tokio::spawn(async move {
-                                    loop {
-                                        tokio::time::sleep(tokio::time::Duration::from_millis(33)).await;
-                                        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
-                                        if atx.send(AudioCommand::GetLevel(reply_tx)).is_err() {
-                                            break;
-                                        }
-                                        if let Ok(level) = reply_rx.await {
-                                            let _ = handle.emit("audio-level", level);
-                                        }
-                                    }
-                                }));
-                            } else {
-                                // Stop recording
-                                is_recording = false;
-
-                                // Stop level polling
-                                if let Some(task) = level_poll_task.take() {
-                                    task.abort();
-                                }
-
-                                let _ = app_handle.emit("badge-state", "processing");
-
-                                let save_path = std::env::temp_dir().join("voiceclip_audio.wav");
-                                let save_path_str = save_path.to_string_lossy().to_string();
-
-                                let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
-                                let _ = audio_tx_orch.send(AudioCommand::StopAndSave(save_path_str, reply_tx));
-
-                                let stop_res = reply_rx.await.unwrap_or_else(|_| Err(anyhow::anyhow!("Audio thread died")));
-
-                                match stop_res {
-                                    Ok(()) => {
-                                        let start_time = std::time::Instant::now();
-                                        let model_dir = Config::get_models_dir().unwrap_or_else(|_| std::env::temp_dir());
-                                        let model_name = {
-                                            let state = app_handle.state::<AppState>();
-                                            let cfg = state.config.lock().unwrap();
-                                            cfg.model_name.clone()
-                                        };
-                                        let model_path = model_dir.join(&model_name);
-                                        let model_path_clone = model_path.clone();
-
-                                        match whisper::transcribe(&save_path, &model_path).await {
-                                            Ok(text) => {
-                                                let latency_ms = start_time.elapsed().as_millis() as u32;
-                                                println!("Transcription output: {}", text);
-
-                                                {
-                                                    let text_clone = text.clone();
-                                                    let append = config_for_orchestrator.append_mode;
-                                                    if let Err(e) = tokio::task::spawn_blocking(move || {
-                                                        delivery::copy_to_clipboard(&text_clone, append)
-                                                    }).await.unwrap_or_else(|e| Err(anyhow::anyhow!(e))) {
-                                                        eprintln!("Delivery failed: {:?}", e);
-                                                    }
-                                                }
-                                                {
-                                                    let text_clone = text.clone();
-                                                    let _ = tokio::task::spawn_blocking(move || {
-                                                        delivery::notify("VoiceClip Success", &text_clone, false)
-                                                    }).await;
-                                                }
-
-                                                let word_count = text.split_whitespace().count() as u32;
-                                                let duration_secs = (db::current_timestamp() - recording_start_time) as u32;
-
-                                                let session = db::SessionRecord {
-                                                    started_at: recording_start_time,
-                                                    duration_secs,
-                                                    word_count,
-                                                    model_used: model_path_clone.to_string_lossy().to_string(),
-                                                    transcription: text,
-                                                    latency_ms,
-                                                    error: None,
-                                                };
-
-                                                {
-                                                    let state = app_handle.state::<AppState>();
-                                                    let _ = state.db.lock().map(|db| db.log_session(session));
-                                                }
-
-                                                let _ = app_handle.emit("badge-state", "success");
-
-                                                let handle = app_handle.clone();
-                                                tokio::spawn(async move {
-                                                    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                                                    let _ = handle.emit("badge-state", "idle");
-                                                });
-                                            }
-                                            Err(e) => {
-                                                eprintln!("Transcription failed: {:?}", e);
-                                                let _ = app_handle.emit("badge-state", "error");
-                                                let _ = tokio::task::spawn_blocking(|| {
-                                                    delivery::notify("VoiceClip Error", "Failed to transcribe audio", true)
-                                                }).await;
-
-                                                let duration_secs = (db::current_timestamp() - recording_start_time) as u32;
-                                                let session = db::SessionRecord {
-                                                    started_at: recording_start_time,
-                                                    duration_secs,
-                                                    word_count: 0,
-                                                    model_used: model_path.to_string_lossy().to_string(),
-                                                    transcription: String::new(),
-                                                    latency_ms: start_time.elapsed().as_millis() as u32,
-                                                    error: Some(e.to_string()),
-                                                };
-                                                {
-                                                    let state = app_handle.state::<AppState>();
-                                                    let _ = state.db.lock().map(|db| db.log_session(session));
-                                                }
-
-                                                let handle = app_handle.clone();
-                                                tokio::spawn(async move {
-                                                    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                                                    let _ = handle.emit("badge-state", "idle");
-                                                });
-                                            }
-                                        }
-                                    }
-                                    Err(_) => {
-                                        eprintln!("Failed to save recording.");
-                                        let _ = app_handle.emit("badge-state", "error");
-                                        let handle = app_handle.clone();
-                                        tokio::spawn(async move {
-                                            tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-                                            let _ = handle.emit("badge-state", "idle");
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
+                    orchestrator.handle_event(event).await;
                 }
             });
 
@@ -344,15 +930,154 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             toggle_recording,
+            cancel_recording,
             get_config,
             save_config,
             list_input_devices,
+            list_models,
+            set_model,
             get_stats_summary,
             get_recent_sessions,
+            get_session_segments,
+            retry_session,
+            get_session_audio,
+            retranscribe,
+            get_transcripts,
+            list_vocabulary,
+            add_vocabulary_term,
+            remove_vocabulary_term,
+            list_rules,
+            add_rule,
+            update_rule,
+            remove_rule,
+            preview_rule,
+            list_snippets,
+            add_snippet,
+            update_snippet,
+            remove_snippet,
             open_settings_window,
             open_stats_window,
             save_badge_position,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let state = app.state::<AppState>();
+                let transcriber = state.transcriber.lock().map(|t| t.clone());
+                if let Ok(transcriber) = transcriber {
+                    transcriber.shutdown();
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, pattern: &str, replacement: &str) -> db::ReplacementRule {
+        db::ReplacementRule {
+            id,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            is_regex: false,
+            case_sensitive: false,
+            whole_word: true,
+            enabled: true,
+        }
+    }
+
+    fn session(id: i64, raw: &str, transcription: &str) -> db::SessionRow {
+        db::SessionRow {
+            id,
+            started_at: id,
+            duration_secs: 1,
+            word_count: 1,
+            transcription: transcription.to_string(),
+            latency_ms: 0,
+            truncated: false,
+            cancelled: false,
+            no_speech: false,
+            whisper_options: None,
+            language: Some("en".to_string()),
+            avg_logprob: None,
+            error: None,
+            error_kind: None,
+            failed_audio_path: None,
+            audio_path: None,
+            model_used: String::new(),
+            fallback_model: None,
+            raw_transcription: Some(raw.to_string()),
+        }
+    }
+
+    fn default_filter() -> filter::TranscriptFilter {
+        filter::TranscriptFilter::new(&config::FilterOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn previews_the_candidate_on_raw_text_after_saved_rules() {
+        let options = config::PostProcessOptions::default();
+        let saved = [rule(1, "get hub", "GitHub")];
+        let sessions = vec![
+            // Delivered text no longer contains "hub", so only the raw text shows the candidate's effect.
+            session(1, "um push to get hub", "Push to GitHub"),
+            session(2, "open the hub page", "Open the hub page"),
+            session(3, "nothing to change", "Nothing to change"),
+        ];
+        let candidate = rule(0, "hub", "Hub");
+
+        let previews = rule_previews(&default_filter(), &options, &saved, candidate, sessions).unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].session_id, 2);
+        assert_eq!(previews[0].before, "Open the hub page");
+        assert_eq!(previews[0].after, "Open the Hub page");
+    }
+
+    #[test]
+    fn previews_only_text_the_filter_kept() {
+        let options = config::PostProcessOptions::default();
+        let sessions = vec![
+            session(1, "[BLANK_AUDIO] audio check", "Audio check"),
+            session(2, "Thanks for watching!", "Thanks for watching!"),
+        ];
+        let candidate = rule(0, "blank_audio", "blank");
+        assert!(rule_previews(&default_filter(), &options, &[], candidate, sessions.clone()).unwrap().is_empty());
+
+        let previews = rule_previews(&default_filter(), &options, &[], rule(0, "audio", "sound"), sessions).unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].before, "Audio check");
+        assert_eq!(previews[0].after, "Sound check");
+
+        let watching = vec![session(2, "Thanks for watching!", "Thanks for watching!")];
+        assert!(rule_previews(&default_filter(), &options, &[], rule(0, "watching", "reading"), watching)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn editing_a_rule_replaces_the_saved_version() {
+        let options = config::PostProcessOptions::default();
+        let saved = [rule(1, "get hub", "GitHub")];
+        let sessions = vec![session(1, "push to get hub", "Push to GitHub")];
+
+        let previews = rule_previews(&default_filter(), &options, &saved, rule(1, "get hub", "GitLab"), sessions).unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].before, "Push to GitHub");
+        assert_eq!(previews[0].after, "Push to GitLab");
+    }
+
+    #[test]
+    fn preview_language_follows_translation_and_detection() {
+        let mut s = session(1, "", "");
+        assert_eq!(preview_language(&s), "en");
+        s.language = Some("pt".to_string());
+        assert_eq!(preview_language(&s), "pt");
+        s.whisper_options = Some(r#"{"translate":true}"#.to_string());
+        assert_eq!(preview_language(&s), "en");
+        s.language = None;
+        s.whisper_options = Some(r#"{"language":"de"}"#.to_string());
+        assert_eq!(preview_language(&s), "de");
+    }
 }
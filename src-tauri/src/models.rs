@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// "ggml" read as a little-endian u32, at the start of every whisper.cpp model.
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+/// Multilingual models have one more token than English-only ones (51864).
+const MULTILINGUAL_VOCAB: i32 = 51865;
+/// whisper.cpp stores the quantization version as `ftype / 1000`.
+const QNT_VERSION_FACTOR: i32 = 1000;
+
+/// A whisper model found in the models directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    /// File name, usable as `model_name` in the config.
+    pub name: String,
+    pub size_bytes: u64,
+    /// e.g. "base", "large-v3".
+    pub model_type: String,
+    /// Weight type, e.g. "f16" or "q5_1".
+    pub quantization: String,
+    pub multilingual: bool,
+}
+
+/// Every readable ggml model in `dir`, sorted by name. Other files are skipped.
+pub fn list_models(dir: &Path) -> Result<Vec<ModelInfo>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read models directory {:?}", dir)),
+    };
+    let mut models: Vec<ModelInfo> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|entry| read_info(&entry.path()).ok())
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+/// Path of the model called `name`. Accepts the exact file name as well as
+/// whisper.cpp's download names, so "base.en" also finds `ggml-base.en.bin`.
+pub fn resolve(dir: &Path, name: &str) -> PathBuf {
+    [name.to_string(), format!("ggml-{}.bin", name), format!("{}.bin", name)]
+        .iter()
+        .map(|candidate| dir.join(candidate))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| dir.join(name))
+}
+
+/// Fails with the list of available models if `name` is missing or not a ggml model.
+pub fn validate(dir: &Path, name: &str) -> Result<ModelInfo> {
+    read_info(&resolve(dir, name)).map_err(|e| {
+        let available = list_models(dir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|m| m.name)
+            .collect::<Vec<_>>();
+        if available.is_empty() {
+            e.context(format!("Model '{}' is not usable and no models were found in {:?}", name, dir))
+        } else {
+            e.context(format!("Model '{}' is not usable. Available: {}", name, available.join(", ")))
+        }
+    })
+}
+
+/// Reads the model's hyperparameters from its ggml header.
+pub fn read_info(path: &Path) -> Result<ModelInfo> {
+    let mut file = File::open(path).with_context(|| format!("Model not found at {:?}", path))?;
+    let size_bytes = file.metadata()?.len();
+    let mut header = [0u8; 48];
+    file.read_exact(&mut header)
+        .with_context(|| format!("{:?} is too short to be a whisper model", path))?;
+
+    let field = |i: usize| i32::from_le_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+    if field(0) as u32 != GGML_MAGIC {
+        anyhow::bail!("{:?} is not a ggml whisper model", path);
+    }
+    // Hyperparameters in file order, after the magic.
+    let n_vocab = field(1);
+    let n_audio_layer = field(5);
+    let n_text_layer = field(9);
+    let n_mels = field(10);
+    let ftype = field(11) % QNT_VERSION_FACTOR;
+
+    Ok(ModelInfo {
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        size_bytes,
+        model_type: model_type(n_audio_layer, n_text_layer, n_mels).to_string(),
+        quantization: quantization(ftype),
+        multilingual: n_vocab >= MULTILINGUAL_VOCAB,
+    })
+}
+
+fn model_type(n_audio_layer: i32, n_text_layer: i32, n_mels: i32) -> &'static str {
+    match (n_audio_layer, n_text_layer, n_mels) {
+        (4, _, _) => "tiny",
+        (6, _, _) => "base",
+        (12, _, _) => "small",
+        (24, _, _) => "medium",
+        (32, 4, _) => "large-v3-turbo",
+        (32, _, 128) => "large-v3",
+        (32, _, _) => "large",
+        _ => "unknown",
+    }
+}
+
+/// Names of ggml's `ftype` values.
+fn quantization(ftype: i32) -> String {
+    match ftype {
+        0 => "f32",
+        1 => "f16",
+        2 => "q4_0",
+        3 => "q4_1",
+        7 => "q8_0",
+        8 => "q5_0",
+        9 => "q5_1",
+        10 => "q2_k",
+        11 => "q3_k",
+        12 => "q4_k",
+        13 => "q5_k",
+        14 => "q6_k",
+        other => return format!("unknown ({})", other),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("voiceclip-models-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A ggml header with whisper.cpp's hyperparameters in file order, then some weights.
+    fn header(n_vocab: i32, audio_layers: i32, text_layers: i32, n_mels: i32, ftype: i32) -> Vec<u8> {
+        let fields = [GGML_MAGIC as i32, n_vocab, 1500, 512, 8, audio_layers, 448, 512, 8, text_layers, n_mels, ftype];
+        let mut bytes: Vec<u8> = fields.iter().flat_map(|f| f.to_le_bytes()).collect();
+        bytes.extend([0u8; 16]);
+        bytes
+    }
+
+    #[test]
+    fn reads_type_quantization_and_language_support() {
+        let dir = scratch_dir("info");
+        let cases = [
+            // file, header, type, quantization, multilingual
+            ("ggml-base.en.bin", header(51864, 6, 6, 80, 1), "base", "f16", false),
+            ("ggml-base.en-q5_1.bin", header(51864, 6, 6, 80, 1009), "base", "q5_1", false),
+            ("ggml-small.bin", header(51865, 12, 12, 80, 0), "small", "f32", true),
+            ("ggml-large-v3.bin", header(51866, 32, 32, 128, 2008), "large-v3", "q5_0", true),
+            ("ggml-large-v3-turbo.bin", header(51866, 32, 4, 128, 1), "large-v3-turbo", "f16", true),
+            ("ggml-large-v2.bin", header(51865, 32, 32, 80, 1), "large", "f16", true),
+            ("odd.bin", header(51865, 5, 5, 80, 99), "unknown", "unknown (99)", true),
+        ];
+        for (name, bytes, _, _, _) in &cases {
+            fs::write(dir.join(name), bytes).unwrap();
+        }
+        let infos: Vec<_> = cases.iter().map(|(name, ..)| read_info(&dir.join(name))).collect();
+        let _ = fs::remove_dir_all(&dir);
+
+        for ((name, bytes, model_type, quantization, multilingual), info) in cases.iter().zip(infos) {
+            let info = info.unwrap();
+            assert_eq!(info.name, *name);
+            assert_eq!(info.size_bytes, bytes.len() as u64);
+            assert_eq!(info.model_type, *model_type, "{}", name);
+            assert_eq!(info.quantization, *quantization, "{}", name);
+            assert_eq!(info.multilingual, *multilingual, "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_ggml_models() {
+        let dir = scratch_dir("invalid");
+        let mut gguf = header(51865, 6, 6, 80, 1);
+        gguf[..4].copy_from_slice(b"GGUF");
+        fs::write(dir.join("bad-magic.bin"), gguf).unwrap();
+        fs::write(dir.join("truncated.bin"), &header(51865, 6, 6, 80, 1)[..40]).unwrap();
+
+        let bad_magic = read_info(&dir.join("bad-magic.bin"));
+        let truncated = read_info(&dir.join("truncated.bin"));
+        let missing = read_info(&dir.join("missing.bin"));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(format!("{:#}", bad_magic.unwrap_err()).contains("not a ggml whisper model"));
+        assert!(format!("{:#}", truncated.unwrap_err()).contains("too short"));
+        assert!(format!("{:#}", missing.unwrap_err()).contains("not found"));
+    }
+
+    #[test]
+    fn resolves_download_names_and_lists_only_models() {
+        let dir = scratch_dir("resolve");
+        fs::write(dir.join("ggml-base.en.bin"), header(51864, 6, 6, 80, 1)).unwrap();
+        fs::write(dir.join("tiny"), header(51865, 4, 4, 80, 1)).unwrap();
+        fs::write(dir.join("notes.txt"), "not a model").unwrap();
+
+        let resolved = [resolve(&dir, "base.en"), resolve(&dir, "ggml-base.en.bin"), resolve(&dir, "tiny"), resolve(&dir, "gone")];
+        let listed = list_models(&dir).unwrap().into_iter().map(|m| m.name).collect::<Vec<_>>();
+        let valid = validate(&dir, "base.en").map(|m| m.model_type);
+        let missing = validate(&dir, "medium").map(|m| m.name);
+        let unreadable = validate(&dir, "notes.txt").map(|m| m.name);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(resolved, [dir.join("ggml-base.en.bin"), dir.join("ggml-base.en.bin"), dir.join("tiny"), dir.join("gone")]);
+        assert_eq!(listed, ["ggml-base.en.bin", "tiny"]);
+        assert_eq!(valid.unwrap(), "base");
+        let message = format!("{:#}", missing.unwrap_err());
+        assert!(message.contains("Available: ggml-base.en.bin, tiny"), "{}", message);
+        assert!(unreadable.is_err());
+    }
+
+    #[test]
+    fn missing_models_directory_lists_nothing() {
+        let dir = std::env::temp_dir().join(format!("voiceclip-models-absent-{}", std::process::id()));
+        assert!(list_models(&dir).unwrap().is_empty());
+        let message = format!("{:#}", validate(&dir, "base.en").unwrap_err());
+        assert!(message.contains("no models were found"), "{}", message);
+    }
+}
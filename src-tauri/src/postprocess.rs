@@ -0,0 +1,520 @@
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use crate::config::{PostProcessOptions, PostProcessStep, SpokenGrammar};
+use crate::db::ReplacementRule;
+
+/// Runs the user's replacement rules and then the configured chain of text
+/// transforms on a transcription before it is delivered.
+pub struct PostProcessor {
+    rules: Vec<CompiledRule>,
+    steps: Vec<PostProcessStep>,
+    /// `None` when there are no filler words to remove.
+    fillers: Option<Regex>,
+    /// Keyed by language code.
+    spoken: HashMap<String, SpokenCommands>,
+}
+
+impl PostProcessor {
+    /// Disabled rules are skipped, and so are invalid ones so that a bad rule
+    /// cannot break transcription.
+    pub fn new(options: &PostProcessOptions, rules: &[ReplacementRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| match CompiledRule::new(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    eprintln!("Skipping replacement rule {}: {:#}", rule.id, e);
+                    None
+                }
+            })
+            .collect();
+        let words: Vec<String> = options
+            .filler_words
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(regex::escape)
+            .collect();
+        let fillers = if words.is_empty() {
+            None
+        } else {
+            // Takes the space before the filler and a comma after it, so
+            // "I think, um, we" becomes "I think, we".
+            let pattern = format!(r"(?i)\s*\b(?:{})\b,?", words.join("|"));
+            Some(Regex::new(&pattern).context("Invalid filler words")?)
+        };
+        let spoken = options
+            .spoken_punctuation
+            .iter()
+            .filter_map(|(language, grammar)| {
+                let commands = SpokenCommands::new(grammar)
+                    .with_context(|| format!("Invalid spoken punctuation for '{}'", language));
+                commands.transpose().map(|c| c.map(|c| (language.to_ascii_lowercase(), c)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules, steps: options.steps.clone(), fillers, spoken })
+    }
+
+    /// `language` picks the spoken punctuation grammar.
+    pub fn apply(&self, text: &str, language: &str) -> String {
+        let text = self.rules.iter().fold(text.to_string(), |text, rule| rule.apply(&text));
+        self.steps.iter().fold(text, |text, step| self.apply_step(*step, text, language))
+    }
+
+    fn apply_step(&self, step: PostProcessStep, text: String, language: &str) -> String {
+        match step {
+            // Line breaks at either end can only come from spoken commands, so they stay.
+            PostProcessStep::Trim => text.trim_matches(|c: char| c.is_whitespace() && c != '\n').to_string(),
+            PostProcessStep::NormalizeWhitespace => normalize_whitespace(&text),
+            PostProcessStep::RemoveFillers => match &self.fillers {
+                Some(fillers) => fillers.replace_all(&text, "").into_owned(),
+                None => text,
+            },
+            PostProcessStep::Capitalize => capitalize_sentences(&text),
+            PostProcessStep::AddTrailingPeriod => add_trailing_period(&text),
+            PostProcessStep::RemoveTrailingPeriod => remove_trailing_period(&text),
+            PostProcessStep::SpokenPunctuation => match self.spoken.get(language) {
+                Some(commands) => commands.apply(&text),
+                None => text,
+            },
+        }
+    }
+}
+
+/// A [`ReplacementRule`] turned into a regex.
+pub struct CompiledRule {
+    regex: Regex,
+    replacement: String,
+    /// Regex rules expand `$1`-style references; literal ones insert the text as-is.
+    expand: bool,
+}
+
+impl CompiledRule {
+    /// Fails if the pattern is empty or not a valid regex.
+    pub fn new(rule: &ReplacementRule) -> Result<Self> {
+        if rule.pattern.trim().is_empty() {
+            anyhow::bail!("Pattern cannot be empty");
+        }
+        let mut pattern = if rule.is_regex {
+            format!("(?:{})", rule.pattern)
+        } else {
+            // Whisper's spacing varies, so any run of whitespace matches a space.
+            rule.pattern.split_whitespace().map(regex::escape).collect::<Vec<_>>().join(r"\s+")
+        };
+        if rule.whole_word {
+            // `\b` only matches next to a word character, so a literal such as
+            // "C++" gets a boundary on its word-character side only.
+            let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if rule.is_regex || is_word(rule.pattern.chars().next()) {
+                pattern = format!(r"\b{}", pattern);
+            }
+            if rule.is_regex || is_word(rule.pattern.chars().last()) {
+                pattern = format!(r"{}\b", pattern);
+            }
+        }
+        if !rule.case_sensitive {
+            pattern = format!("(?i){}", pattern);
+        }
+        let regex = Regex::new(&pattern).with_context(|| format!("Invalid pattern '{}'", rule.pattern))?;
+        Ok(Self { regex, replacement: rule.replacement.clone(), expand: rule.is_regex })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        if self.expand {
+            self.regex.replace_all(text, self.replacement.as_str()).into_owned()
+        } else {
+            self.regex.replace_all(text, regex::NoExpand(&self.replacement)).into_owned()
+        }
+    }
+}
+
+/// A [`SpokenGrammar`] turned into a single regex over all of its phrases.
+struct SpokenCommands {
+    regex: Regex,
+    /// A command phrase at the start of the text.
+    leading: Regex,
+    /// A command phrase at the end of the text.
+    trailing: Regex,
+    /// Normalized phrase to inserted text.
+    commands: HashMap<String, String>,
+}
+
+impl SpokenCommands {
+    /// `None` if the grammar has no commands.
+    fn new(grammar: &SpokenGrammar) -> Result<Option<Self>> {
+        let commands: HashMap<String, String> = grammar
+            .commands
+            .iter()
+            .map(|(phrase, text)| (normalize_phrase(phrase), text.clone()))
+            .filter(|(phrase, _)| !phrase.is_empty())
+            .collect();
+        if commands.is_empty() {
+            return Ok(None);
+        }
+
+        // Longest first, so "new paragraph" wins over a shorter command it starts with.
+        let mut phrases: Vec<&String> = commands.keys().collect();
+        phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        let alternatives = phrases.iter().map(|p| words_pattern(p)).collect::<Vec<_>>().join("|");
+        let escape = normalize_phrase(&grammar.escape);
+        let escape = if escape.is_empty() { String::new() } else { format!(r"(?:(?P<esc>{})\s+)?", words_pattern(&escape)) };
+        // whisper tends to punctuate around command words ("Hello, comma, world."),
+        // so punctuation next to a command is taken along with it.
+        let pattern = format!(r"(?i)(?P<pre>[\s,.;:!?]*)\b{}(?P<cmd>{})\b(?P<post>[,.;:!?]*\s*)", escape, alternatives);
+        Ok(Some(Self {
+            regex: Regex::new(&pattern)?,
+            leading: Regex::new(&format!(r"(?i)^(?:{})\b", alternatives))?,
+            trailing: Regex::new(&format!(r"(?i)\b(?:{})$", alternatives))?,
+            commands,
+        }))
+    }
+
+    fn apply(&self, text: &str) -> String {
+        if !self.regex.is_match(text) {
+            return text.to_string();
+        }
+        let replaced = self.regex.replace_all(text, |caps: &Captures| {
+            let (pre, command, post) = (&caps["pre"], &caps["cmd"], &caps["post"]);
+            if caps.name("esc").is_some() {
+                return format!("{}{}{}", pre, command, post);
+            }
+            let span = caps.name("cmd").expect("cmd always matches");
+            if !self.stands_apart(&text[..span.start()], &text[span.end()..]) {
+                return caps[0].to_string();
+            }
+            let Some(inserted) = self.commands.get(&normalize_phrase(command)) else {
+                return caps[0].to_string();
+            };
+            // Punctuation whisper put before the command marks the pause, so it is
+            // dropped, except before a line break so "Done. New line" keeps its period.
+            if inserted.starts_with('\n') {
+                let kept: String = pre.chars().filter(|c| !c.is_whitespace()).collect();
+                format!("{}{}", kept, inserted)
+            } else {
+                inserted.clone()
+            }
+        });
+        tidy_spaces(&replaced)
+    }
+
+    /// A phrase is only a command when a pause sets it apart on both sides:
+    /// punctuation, the start or end of the text, or another command. So "the
+    /// period of time" is left alone while "Hello, comma, world" is not.
+    fn stands_apart(&self, before: &str, after: &str) -> bool {
+        let before = before.trim_end();
+        let after = after.trim_start();
+        let apart_before = before.is_empty() || before.ends_with(PUNCTUATION) || self.trailing.is_match(before);
+        let apart_after = after.is_empty() || after.starts_with(PUNCTUATION) || self.leading.is_match(after);
+        apart_before && apart_after
+    }
+}
+
+/// Punctuation whisper writes where the speaker paused.
+const PUNCTUATION: [char; 6] = [',', '.', ';', ':', '!', '?'];
+
+fn normalize_phrase(phrase: &str) -> String {
+    phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Matches the words of `phrase` separated by any whitespace.
+fn words_pattern(phrase: &str) -> String {
+    phrase.split_whitespace().map(regex::escape).collect::<Vec<_>>().join(r"\s+")
+}
+
+/// Collapses runs of spaces and removes spaces at the start and end of lines.
+fn tidy_spaces(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.split(' ').filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Collapses whitespace within each line, keeping line breaks, and removes
+/// spaces before punctuation.
+fn normalize_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let mut out = String::with_capacity(line.len());
+            for word in line.split_whitespace() {
+                let attaches = word.starts_with([',', '.', '!', '?', ';', ':']);
+                if !out.is_empty() && !attaches {
+                    out.push(' ');
+                }
+                out.push_str(word);
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Uppercases the first letter of the text, of every line, and of every word
+/// following a sentence-ending `.`, `!` or `?` and whitespace. "3.5" stays
+/// intact, and so does the word after a common abbreviation such as "e.g.".
+fn capitalize_sentences(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut capitalize = true;
+    let mut after_terminator = false;
+    // The current run of non-whitespace, to recognize abbreviations.
+    let mut word = String::new();
+    for c in text.chars() {
+        if capitalize && c.is_alphanumeric() {
+            out.extend(c.to_uppercase());
+            word.push(c);
+            capitalize = false;
+            after_terminator = false;
+            continue;
+        }
+        if matches!(c, '.' | '!' | '?') {
+            after_terminator = true;
+        } else if c.is_whitespace() {
+            if (after_terminator && !is_abbreviation(&word)) || c == '\n' {
+                capitalize = true;
+            }
+            after_terminator = false;
+            word.clear();
+        } else {
+            after_terminator = false;
+            if c.is_alphanumeric() {
+                capitalize = false;
+            }
+        }
+        if !c.is_whitespace() {
+            word.push(c);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Abbreviations that rarely end a sentence, compared in lowercase.
+const ABBREVIATIONS: [&str; 10] = ["e.g.", "i.e.", "vs.", "cf.", "approx.", "mr.", "mrs.", "ms.", "dr.", "st."];
+
+fn is_abbreviation(word: &str) -> bool {
+    let word = word.trim_start_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+    ABBREVIATIONS.contains(&word.as_str())
+}
+
+fn add_trailing_period(text: &str) -> String {
+    let trimmed = text.trim_end().trim_end_matches([',', ';', ':']);
+    if trimmed.is_empty() || trimmed.ends_with(['.', '!', '?', '…']) {
+        return trimmed.to_string();
+    }
+    format!("{}.", trimmed)
+}
+
+/// Removes one trailing period but leaves an ellipsis alone.
+fn remove_trailing_period(text: &str) -> String {
+    let trimmed = text.trim_end();
+    match trimmed.strip_suffix('.') {
+        Some(rest) if !rest.ends_with('.') => rest.to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(steps: &[PostProcessStep]) -> PostProcessor {
+        let options = PostProcessOptions { steps: steps.to_vec(), ..PostProcessOptions::default() };
+        PostProcessor::new(&options, &[]).unwrap()
+    }
+
+    fn spoken(text: &str, language: &str) -> String {
+        processor(&[PostProcessStep::SpokenPunctuation]).apply(text, language)
+    }
+
+    /// The default chain with spoken punctuation in front, as the guide suggests.
+    fn spoken_chain(text: &str) -> String {
+        processor(&[
+            PostProcessStep::SpokenPunctuation,
+            PostProcessStep::RemoveFillers,
+            PostProcessStep::NormalizeWhitespace,
+            PostProcessStep::Trim,
+            PostProcessStep::Capitalize,
+        ])
+        .apply(text, "en")
+    }
+
+    #[test]
+    fn english_commands_set_apart_by_pauses() {
+        let cases = [
+            ("Hello, comma, world.", "Hello, world."),
+            ("First point, comma, second point. New line.", "First point, second point.\n"),
+            ("Is it done? Question mark.", "Is it done?"),
+            ("Wait, colon, it works. Full stop.", "Wait: it works."),
+            ("Options, open paren, two, close paren.", "Options (two)"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(spoken(input, "en"), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn command_words_in_ordinary_speech_are_kept() {
+        for text in [
+            "The period of time was short.",
+            "During the period.",
+            "Add a new line of code",
+            "Put a comma here",
+        ] {
+            assert_eq!(spoken(text, "en"), text);
+        }
+        assert_eq!(spoken_chain("the period of time"), "The period of time");
+    }
+
+    #[test]
+    fn portuguese_commands() {
+        assert_eq!(spoken("Olá, vírgula, mundo.", "pt"), "Olá, mundo.");
+        assert_eq!(spoken("Primeiro item. Nova linha. Segundo item.", "pt"), "Primeiro item.\nSegundo item.");
+        assert_eq!(spoken("Tudo certo, ponto de interrogação.", "pt"), "Tudo certo?");
+        assert_eq!(spoken("Lista, dois pontos, pão.", "pt"), "Lista: pão.");
+    }
+
+    #[test]
+    fn spanish_commands() {
+        assert_eq!(spoken("Hola, coma, mundo.", "es"), "Hola, mundo.");
+        assert_eq!(spoken("Uno. Punto y coma. Dos.", "es"), "Uno; Dos.");
+        assert_eq!(spoken("Listo, signo de interrogación.", "es"), "Listo?");
+    }
+
+    #[test]
+    fn unknown_language_is_left_alone() {
+        assert_eq!(spoken("Hallo, comma, Welt.", "de"), "Hallo, comma, Welt.");
+    }
+
+    #[test]
+    fn literal_escape_types_the_command_words() {
+        assert_eq!(spoken("Type the word, literal comma, here.", "en"), "Type the word, comma, here.");
+        assert_eq!(spoken("The literal period of time", "en"), "The period of time");
+        assert_eq!(spoken("Diga, literal vírgula, agora.", "pt"), "Diga, vírgula, agora.");
+    }
+
+    #[test]
+    fn quotes_pair_around_the_quoted_words() {
+        assert_eq!(spoken("He said, open quote, hello, close quote. Then left.", "en"), "He said \"hello\" Then left.");
+        assert_eq!(spoken_chain("open quote, yes, close quote."), "\"Yes\"");
+    }
+
+    #[test]
+    fn line_breaks_survive_the_default_chain() {
+        assert_eq!(spoken_chain("New paragraph."), "\n\n");
+        assert_eq!(spoken_chain("Done. New line. Next."), "Done.\nNext.");
+        assert_eq!(spoken_chain("New paragraph. Second part."), "\n\nSecond part.");
+    }
+
+    #[test]
+    fn bullet_points_start_new_lines() {
+        assert_eq!(
+            spoken_chain("Shopping list. Bullet point, milk. Bullet point, eggs."),
+            "Shopping list.\n- Milk.\n- Eggs."
+        );
+        assert_eq!(spoken_chain("Bullet point, milk."), "\n- Milk.");
+    }
+
+    #[test]
+    fn capitalize_sentences_table() {
+        let cases = [
+            ("hello world", "Hello world"),
+            ("one. two! three? four", "One. Two! Three? Four"),
+            ("version 3.5 is out", "Version 3.5 is out"),
+            ("fruit, e.g. apples, i.e. food", "Fruit, e.g. apples, i.e. food"),
+            ("e.g. this", "E.g. this"),
+            ("ask dr. smith. then go", "Ask dr. smith. Then go"),
+            ("see (e.g. this) now", "See (e.g. this) now"),
+            ("first line\nsecond line", "First line\nSecond line"),
+            ("\n- milk\n- eggs", "\n- Milk\n- Eggs"),
+            ("done...  next", "Done...  Next"),
+            ("émile. ça va", "Émile. Ça va"),
+            ("", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(capitalize_sentences(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn filler_removal_table() {
+        let fillers = processor(&[PostProcessStep::RemoveFillers]);
+        let cases = [
+            ("um hello", " hello"),
+            ("I think, um, we should", "I think, we should"),
+            ("Uh, UMM yes", " yes"),
+            ("the drum and the umbrella", "the drum and the umbrella"),
+            ("hmm", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(fillers.apply(input, "en"), expected, "{:?}", input);
+        }
+        let options = PostProcessOptions {
+            steps: vec![PostProcessStep::RemoveFillers],
+            filler_words: vec!["  ".to_string()],
+            ..PostProcessOptions::default()
+        };
+        assert_eq!(PostProcessor::new(&options, &[]).unwrap().apply("um ok", "en"), "um ok");
+    }
+
+    #[test]
+    fn normalize_whitespace_table() {
+        let cases = [
+            ("a   b\tc", "a b c"),
+            ("  padded  ", "padded"),
+            ("Hello , world !", "Hello, world!"),
+            ("line one  \n  line two", "line one\nline two"),
+            ("wait ... what ?", "wait... what?"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_whitespace(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn trailing_period_table() {
+        let add = [
+            ("Hello", "Hello."),
+            ("Hello.", "Hello."),
+            ("Really?", "Really?"),
+            ("Wait…", "Wait…"),
+            ("List,", "List."),
+            ("Hello  ", "Hello."),
+            ("", ""),
+        ];
+        for (input, expected) in add {
+            assert_eq!(add_trailing_period(input), expected, "{:?}", input);
+        }
+        let remove = [
+            ("Hello.", "Hello"),
+            ("Hello. ", "Hello"),
+            ("Wait...", "Wait..."),
+            ("Really?", "Really?"),
+            ("v1.2", "v1.2"),
+        ];
+        for (input, expected) in remove {
+            assert_eq!(remove_trailing_period(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn trim_keeps_line_breaks() {
+        assert_eq!(processor(&[PostProcessStep::Trim]).apply("  text \t", "en"), "text");
+        assert_eq!(processor(&[PostProcessStep::Trim]).apply(" \n- item ", "en"), "\n- item");
+    }
+
+    #[test]
+    fn rules_run_before_the_steps() {
+        let rules = [ReplacementRule {
+            id: 1,
+            pattern: "get hub".to_string(),
+            replacement: "GitHub".to_string(),
+            is_regex: false,
+            case_sensitive: false,
+            whole_word: true,
+            enabled: true,
+        }];
+        let processor = PostProcessor::new(&PostProcessOptions::default(), &rules).unwrap();
+        assert_eq!(processor.apply("um push to get   hub", "en"), "Push to GitHub");
+    }
+}
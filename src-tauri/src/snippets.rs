@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use crate::db::Snippet;
+
+/// Replaces "<keyword> <trigger>" in a transcription with the snippet's text.
+/// Runs after post-processing so snippet bodies are inserted verbatim.
+pub struct SnippetExpander {
+    /// `None` when there are no snippets.
+    regex: Option<Regex>,
+    /// Normalized trigger to body.
+    bodies: HashMap<String, String>,
+}
+
+impl SnippetExpander {
+    /// Of two triggers that sound the same, such as "sign-off" and "sign off",
+    /// only the first is used.
+    pub fn new(keyword: &str, snippets: &[Snippet]) -> Result<Self> {
+        let mut bodies: HashMap<String, String> = HashMap::new();
+        for snippet in snippets {
+            let trigger = normalize_trigger(&snippet.trigger);
+            if trigger.is_empty() {
+                continue;
+            }
+            if bodies.contains_key(&trigger) {
+                eprintln!("Skipping snippet '{}': another snippet has the same trigger", snippet.trigger);
+                continue;
+            }
+            bodies.insert(trigger, snippet.body.clone());
+        }
+        if bodies.is_empty() {
+            return Ok(Self { regex: None, bodies });
+        }
+
+        // Longest first, so "sign off formal" wins over "sign off".
+        let mut triggers: Vec<&String> = bodies.keys().collect();
+        triggers.sort_by_key(|t| std::cmp::Reverse(t.len()));
+        let alternatives = triggers.iter().map(|t| words_pattern(t)).collect::<Vec<_>>().join("|");
+        let keyword = normalize_trigger(keyword);
+        let prefix = if keyword.is_empty() { String::new() } else { format!(r"{}[\s,]+", words_pattern(&keyword)) };
+        // Punctuation whisper or post-processing put after the trigger is dropped.
+        let pattern = format!(r"(?i)\b{}(?P<trigger>{})\b[.,!?]?", prefix, alternatives);
+        let regex = Regex::new(&pattern).context("Invalid snippet trigger")?;
+        Ok(Self { regex: Some(regex), bodies })
+    }
+
+    /// `variable` returns the value of a `{name}` placeholder; unknown ones are left as-is.
+    pub fn expand(&self, text: &str, mut variable: impl FnMut(&str) -> Option<String>) -> String {
+        let Some(regex) = &self.regex else {
+            return text.to_string();
+        };
+        regex
+            .replace_all(text, |caps: &Captures| {
+                match self.bodies.get(&normalize_trigger(&caps["trigger"])) {
+                    Some(body) => fill_variables(body, &mut variable),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+/// Built-in placeholders: `{date}`, `{time}` and `{clipboard}`. The clipboard
+/// is read at most once, and only if a snippet uses it.
+pub fn default_variables() -> impl FnMut(&str) -> Option<String> {
+    let mut clipboard: Option<Option<String>> = None;
+    move |name| match name {
+        "date" => Some(chrono::Local::now().format("%Y-%m-%d").to_string()),
+        "time" => Some(chrono::Local::now().format("%H:%M").to_string()),
+        "clipboard" => clipboard
+            .get_or_insert_with(|| crate::delivery::read_clipboard().ok())
+            .clone(),
+        _ => None,
+    }
+}
+
+fn fill_variables(body: &str, variable: &mut impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| variable(&after[..end]).map(|value| (value, end)));
+        match value {
+            Some((value, end)) => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The snippet other than `id` whose trigger is spoken the same way as `trigger`.
+pub fn conflicting<'a>(trigger: &str, id: Option<i64>, snippets: &'a [Snippet]) -> Option<&'a Snippet> {
+    let trigger = normalize_trigger(trigger);
+    snippets.iter().find(|s| Some(s.id) != id && normalize_trigger(&s.trigger) == trigger)
+}
+
+/// Lowercase words separated by single spaces; "Sign-off" becomes "sign off".
+pub fn normalize_trigger(trigger: &str) -> String {
+    trigger
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Matches the words of a normalized trigger separated by spaces or hyphens.
+fn words_pattern(trigger: &str) -> String {
+    trigger.split(' ').map(regex::escape).collect::<Vec<_>>().join(r"[\s-]+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(id: i64, trigger: &str, body: &str) -> Snippet {
+        Snippet { id, trigger: trigger.to_string(), body: body.to_string() }
+    }
+
+    fn no_variables(_: &str) -> Option<String> {
+        None
+    }
+
+    fn expand(snippets: &[Snippet], text: &str) -> String {
+        SnippetExpander::new("snippet", snippets).unwrap().expand(text, no_variables)
+    }
+
+    #[test]
+    fn fills_known_placeholders_and_leaves_the_rest() {
+        let mut variable = |name: &str| match name {
+            "name" => Some("Sam".to_string()),
+            "clipboard" => Some("copied text".to_string()),
+            _ => None,
+        };
+        let cases = [
+            ("Hi {name}!", "Hi Sam!"),
+            ("{clipboard} / {clipboard}", "copied text / copied text"),
+            ("{unknown} stays", "{unknown} stays"),
+            ("{name", "{name"),
+            ("{} and {{name}}", "{} and {Sam}"),
+            ("a { b {name}", "a { b Sam"),
+            ("no placeholders", "no placeholders"),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(fill_variables(body, &mut variable), expected, "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn date_and_time_variables() {
+        let mut variables = default_variables();
+        let date = variables("date").unwrap();
+        assert!(Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap().is_match(&date), "{}", date);
+        let time = variables("time").unwrap();
+        assert!(Regex::new(r"^\d{2}:\d{2}$").unwrap().is_match(&time), "{}", time);
+        assert!(variables("weather").is_none());
+    }
+
+    #[test]
+    fn clipboard_placeholder_is_filled_when_expanding() {
+        let snippets = [snippet(1, "quote", "> {clipboard}")];
+        let expander = SnippetExpander::new("snippet", &snippets).unwrap();
+        let mut reads = 0;
+        let text = expander.expand("snippet quote and snippet quote", |name| {
+            reads += 1;
+            (name == "clipboard").then(|| "pasted".to_string())
+        });
+        assert_eq!(text, "> pasted and > pasted");
+        assert_eq!(reads, 2);
+    }
+
+    #[test]
+    fn hyphens_and_spaces_in_triggers_are_interchangeable() {
+        for trigger in ["sign-off", "sign off", "Sign Off"] {
+            let snippets = [snippet(1, trigger, "Best,\nSam")];
+            for spoken in ["snippet sign off", "snippet sign-off", "Snippet Sign-Off", "snippet  sign   off"] {
+                assert_eq!(expand(&snippets, spoken), "Best,\nSam", "{:?} / {:?}", trigger, spoken);
+            }
+        }
+    }
+
+    #[test]
+    fn punctuation_around_the_keyword_and_trigger() {
+        let snippets = [snippet(1, "sign off", "Best, Sam")];
+        let cases = [
+            ("Thanks. Snippet, sign off.", "Thanks. Best, Sam"),
+            ("Thanks, snippet sign off!", "Thanks, Best, Sam"),
+            ("(snippet sign off)", "(Best, Sam)"),
+            // The keyword must be a word of its own, and is required.
+            ("snippets sign off", "snippets sign off"),
+            ("please sign off", "please sign off"),
+            ("snippet sign offer", "snippet sign offer"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expand(&snippets, input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn longer_triggers_win() {
+        let snippets = [snippet(1, "sign off", "Best"), snippet(2, "sign off formal", "Kind regards")];
+        assert_eq!(expand(&snippets, "snippet sign off formal"), "Kind regards");
+        assert_eq!(expand(&snippets, "snippet sign off"), "Best");
+    }
+
+    #[test]
+    fn triggers_that_sound_the_same_conflict() {
+        let existing = [snippet(1, "sign-off", "Best"), snippet(2, "address", "1 Main St")];
+        assert_eq!(conflicting("Sign Off", None, &existing).map(|s| s.id), Some(1));
+        assert_eq!(conflicting("sign  off", Some(1), &existing).map(|s| s.id), None);
+        assert_eq!(conflicting("sign off", Some(2), &existing).map(|s| s.id), Some(1));
+        assert!(conflicting("sign on", None, &existing).is_none());
+
+        // Already stored duplicates keep the first one instead of whichever was inserted last.
+        let duplicates = [snippet(1, "sign-off", "First"), snippet(2, "sign off", "Second")];
+        assert_eq!(expand(&duplicates, "snippet sign off"), "First");
+    }
+
+    #[test]
+    fn no_keyword_matches_bare_triggers() {
+        let snippets = [snippet(1, "sign off", "Best")];
+        let expander = SnippetExpander::new("", &snippets).unwrap();
+        assert_eq!(expander.expand("ok, sign off.", no_variables), "ok, Best");
+        assert_eq!(SnippetExpander::new("snippet", &[]).unwrap().expand("snippet sign off", no_variables), "snippet sign off");
+    }
+}
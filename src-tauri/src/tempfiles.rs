@@ -0,0 +1,241 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where it can't be checked whether the recording's process is still running,
+/// files older than this in the temp directory were left behind by a crash.
+const STALE_AGE: Duration = Duration::from_secs(60 * 60);
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A uniquely named WAV file in the private temp directory. The recording
+/// and any output whisper wrote next to it are deleted on drop.
+pub struct TempRecording {
+    path: PathBuf,
+}
+
+impl TempRecording {
+    /// Reserves a new, empty file that no other process can have pre-created.
+    pub fn create() -> Result<Self> {
+        let dir = private_temp_dir()?;
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let name = format!(
+            "recording-{}-{}-{}.wav",
+            std::process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.join(name);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&path)
+            .with_context(|| format!("Failed to create temporary recording {:?}", path))?;
+
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the recording into `dir` so it outlives the temp file, and
+    /// returns its new path. Whisper's output files are still deleted.
+    pub fn persist(self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory at {:?}", dir))?;
+        let name = self.path.file_name().context("Recording has no file name")?;
+        let dest = dir.join(name);
+        // The temp directory may be on another filesystem (e.g. a tmpfs), where rename fails.
+        if fs::rename(&self.path, &dest).is_err() {
+            fs::copy(&self.path, &dest)
+                .with_context(|| format!("Failed to copy recording to {:?}", dest))?;
+        }
+        Ok(dest)
+    }
+
+    /// Files whisper-cli writes beside the recording.
+    fn outputs(&self) -> [PathBuf; 2] {
+        [self.path.with_extension("wav.json"), self.path.with_extension("wav.txt")]
+    }
+}
+
+impl Drop for TempRecording {
+    fn drop(&mut self) {
+        for path in std::iter::once(self.path.clone()).chain(self.outputs()) {
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    eprintln!("Failed to delete {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+}
+
+/// Per-user directory for in-flight recordings: `$XDG_RUNTIME_DIR/voiceclip`
+/// when available, otherwise a `tmp` folder in the user's cache directory.
+/// On Unix it is restricted to the owner (0700).
+pub fn private_temp_dir() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("", "", "voiceclip")
+        .context("Could not find project directories")?;
+    let dir = match proj_dirs.runtime_dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => proj_dirs.cache_dir().join("tmp"),
+    };
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create temp directory at {:?}", dir))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // chmod fails unless we own the directory, so this also rejects one
+        // planted by another user.
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+            .with_context(|| format!("Failed to restrict permissions on {:?}", dir))?;
+    }
+
+    Ok(dir)
+}
+
+/// Deletes recordings and whisper output left behind by a crash. Only call
+/// this before anything is recorded. Files of another instance that is still
+/// running are kept.
+pub fn remove_stale() {
+    if let Ok(dir) = private_temp_dir() {
+        remove_stale_in(&dir);
+    }
+}
+
+fn remove_stale_in(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(rest) = name.strip_prefix("recording-") else {
+            continue;
+        };
+        let pid = rest.split(['-', '.']).next().and_then(|pid| pid.parse::<u32>().ok());
+        let is_stale = match pid.and_then(|pid| process_running(pid).map(|running| (pid, running))) {
+            // Nothing has been recorded yet, so a file with our pid is from an
+            // earlier process that had the same pid.
+            Some((pid, running)) => pid == std::process::id() || !running,
+            None => entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > STALE_AGE),
+        };
+        if is_stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// `None` where this can't be checked.
+fn process_running(pid: u32) -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("voiceclip-tempfiles-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn create_reserves_a_unique_empty_file_deleted_on_drop() {
+        let first = TempRecording::create().unwrap();
+        let second = TempRecording::create().unwrap();
+        assert_ne!(first.path(), second.path());
+        let name = first.path().file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with(&format!("recording-{}-", std::process::id())), "{}", name);
+        assert_eq!(fs::metadata(first.path()).unwrap().len(), 0);
+
+        let path = first.path().to_path_buf();
+        let outputs = first.outputs();
+        for output in &outputs {
+            fs::write(output, "{}").unwrap();
+        }
+        drop(first);
+        assert!(!path.exists());
+        assert!(outputs.iter().all(|output| !output.exists()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_the_owner_can_access_recordings() {
+        use std::os::unix::fs::PermissionsExt;
+        let recording = TempRecording::create().unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(recording.path()), 0o600);
+        assert_eq!(mode(&private_temp_dir().unwrap()), 0o700);
+    }
+
+    #[test]
+    fn persist_moves_the_recording_and_deletes_whisper_output() {
+        let dir = scratch_dir("persist");
+        let recording = TempRecording::create().unwrap();
+        fs::write(recording.path(), b"RIFF").unwrap();
+        let source = recording.path().to_path_buf();
+        let output = recording.outputs()[0].clone();
+        fs::write(&output, "{}").unwrap();
+
+        let dest = recording.persist(&dir.join("failed")).unwrap();
+        let kept = fs::read(&dest);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(kept.unwrap(), b"RIFF");
+        assert_eq!(dest.file_name(), source.file_name());
+        assert!(!source.exists());
+        assert!(!output.exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stale_cleanup_keeps_recordings_of_running_processes() {
+        let dir = scratch_dir("stale");
+        let touch = |name: &str| {
+            let path = dir.join(name);
+            fs::write(&path, b"").unwrap();
+            path
+        };
+        // pid 1 is always running; no pid can be this large.
+        let running = touch("recording-1-10-0.wav");
+        let gone = touch("recording-4000000000-10-0.wav");
+        let gone_output = touch("recording-4000000000-10-0.wav.json");
+        let ours = touch(&format!("recording-{}-10-0.wav", std::process::id()));
+        let unknown_fresh = touch("recording-unknown.wav");
+        let unknown_old = touch("recording-unknown-old.wav");
+        let hour_and_a_half_ago = SystemTime::now() - Duration::from_secs(90 * 60);
+        fs::File::options().write(true).open(&unknown_old).unwrap().set_modified(hour_and_a_half_ago).unwrap();
+        let other = touch("notes.txt");
+
+        remove_stale_in(&dir);
+        let result = [&running, &gone, &gone_output, &ours, &unknown_fresh, &unknown_old, &other].map(|p| p.exists());
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(result, [true, false, false, false, true, false, true]);
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Structured result of a transcription, with per-segment timings and
+/// per-word confidence when the backend reports them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<Segment>,
+    pub words: Vec<Word>,
+    /// ISO 639-1 code of the spoken (or forced) language.
+    pub language: Option<String>,
+    /// Mean log-probability of the decoded tokens; closer to 0 is more confident.
+    pub avg_logprob: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    pub avg_logprob: Option<f32>,
+    pub no_speech_prob: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Word {
+    /// Index into [`Transcript::segments`].
+    pub segment: usize,
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub probability: f32,
+}
+
+impl Transcript {
+    /// A transcript with no timing information, for backends that only return text.
+    pub fn from_text(text: &str) -> Self {
+        Self {
+            text: text.trim().to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Words in `segment`, in order.
+    pub fn segment_words(&self, segment: usize) -> impl Iterator<Item = &Word> {
+        self.words.iter().filter(move |w| w.segment == segment)
+    }
+
+    /// Parses the file written by `whisper-cli --output-json-full`.
+    pub fn from_cli_json(json: &str) -> Result<Self> {
+        let output: CliOutput = serde_json::from_str(json)
+            .context("Failed to parse whisper-cli JSON output")?;
+
+        let mut transcript = Transcript {
+            language: output.result.and_then(|r| r.language).and_then(|l| normalize_language(&l)),
+            ..Transcript::default()
+        };
+        let mut all_logprobs = Vec::new();
+
+        for (index, cli_segment) in output.transcription.into_iter().enumerate() {
+            let tokens: Vec<Token> = cli_segment
+                .tokens
+                .iter()
+                .filter(|t| !is_special_token(&t.text))
+                .map(|t| {
+                    let offsets = t.offsets.as_ref().unwrap_or(&cli_segment.offsets);
+                    Token { text: &t.text, start_ms: offsets.from, end_ms: offsets.to, p: t.p.unwrap_or(1.0) }
+                })
+                .collect();
+            let logprobs: Vec<f32> = tokens.iter().map(|t| t.p.max(f32::MIN_POSITIVE).ln()).collect();
+            transcript.words.extend(merge_tokens(index, &tokens));
+
+            transcript.segments.push(Segment {
+                start_ms: cli_segment.offsets.from,
+                end_ms: cli_segment.offsets.to,
+                text: cli_segment.text.trim().to_string(),
+                avg_logprob: mean(&logprobs),
+                no_speech_prob: None,
+            });
+            all_logprobs.extend(logprobs);
+        }
+
+        transcript.text = join_segments(&transcript.segments);
+        transcript.avg_logprob = mean(&all_logprobs);
+        Ok(transcript)
+    }
+
+    /// Parses a whisper-server `/inference` response requested with
+    /// `response_format=verbose_json`.
+    pub fn from_server_json(body: &[u8]) -> Result<Self> {
+        let response: ServerResponse = serde_json::from_slice(body)
+            .context("Failed to parse whisper-server response")?;
+        if let Some(error) = response.error {
+            anyhow::bail!("whisper-server error: {}", error);
+        }
+
+        let mut transcript = Transcript {
+            language: response.language.and_then(|l| normalize_language(&l)),
+            ..Transcript::default()
+        };
+        for (index, server_segment) in response.segments.into_iter().enumerate() {
+            // Despite the name, the server's "words" are whisper's tokens.
+            let tokens: Vec<Token> = server_segment
+                .words
+                .iter()
+                .filter(|w| !is_special_token(&w.word))
+                .map(|w| Token {
+                    text: &w.word,
+                    start_ms: w.start.map_or(seconds_to_ms(server_segment.start), seconds_to_ms),
+                    end_ms: w.end.map_or(seconds_to_ms(server_segment.end), seconds_to_ms),
+                    p: w.probability,
+                })
+                .collect();
+            transcript.words.extend(merge_tokens(index, &tokens));
+            transcript.segments.push(Segment {
+                start_ms: seconds_to_ms(server_segment.start),
+                end_ms: seconds_to_ms(server_segment.end),
+                text: server_segment.text.trim().to_string(),
+                avg_logprob: server_segment.avg_logprob,
+                no_speech_prob: server_segment.no_speech_prob,
+            });
+        }
+
+        let segment_logprobs: Vec<f32> = transcript.segments.iter().filter_map(|s| s.avg_logprob).collect();
+        transcript.avg_logprob = mean(&segment_logprobs);
+        transcript.text = match response.text {
+            Some(text) => text.trim().to_string(),
+            None => join_segments(&transcript.segments),
+        };
+        Ok(transcript)
+    }
+}
+
+#[derive(Deserialize)]
+struct CliOutput {
+    result: Option<CliResult>,
+    #[serde(default)]
+    transcription: Vec<CliSegment>,
+}
+
+#[derive(Deserialize)]
+struct CliResult {
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CliSegment {
+    offsets: CliOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<CliToken>,
+}
+
+#[derive(Deserialize)]
+struct CliOffsets {
+    from: i64,
+    to: i64,
+}
+
+#[derive(Deserialize)]
+struct CliToken {
+    text: String,
+    offsets: Option<CliOffsets>,
+    p: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct ServerResponse {
+    text: Option<String>,
+    error: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    segments: Vec<ServerSegment>,
+}
+
+#[derive(Deserialize)]
+struct ServerSegment {
+    text: String,
+    start: f64,
+    end: f64,
+    avg_logprob: Option<f32>,
+    no_speech_prob: Option<f32>,
+    #[serde(default)]
+    words: Vec<ServerWord>,
+}
+
+/// Timings are left out when the server runs with `--no-timestamps`.
+#[derive(Deserialize)]
+struct ServerWord {
+    word: String,
+    start: Option<f64>,
+    end: Option<f64>,
+    probability: f32,
+}
+
+/// A decoded token, from either backend.
+struct Token<'a> {
+    text: &'a str,
+    start_ms: i64,
+    end_ms: i64,
+    p: f32,
+}
+
+/// Joins whisper's sub-word tokens into words. A token with a leading space
+/// starts a new word; anything else, including punctuation, continues the
+/// current one. A word's probability is that of its least likely token.
+fn merge_tokens(segment: usize, tokens: &[Token]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut starts_word = true;
+    for token in tokens {
+        if token.text.trim().is_empty() {
+            starts_word = true;
+            continue;
+        }
+        match words.last_mut() {
+            Some(word) if !starts_word && !token.text.starts_with(char::is_whitespace) => {
+                word.text.push_str(token.text);
+                word.end_ms = token.end_ms;
+                word.probability = word.probability.min(token.p);
+            }
+            _ => words.push(Word {
+                segment,
+                text: token.text.trim().to_string(),
+                start_ms: token.start_ms,
+                end_ms: token.end_ms,
+                probability: token.p,
+            }),
+        }
+        starts_word = token.text.ends_with(char::is_whitespace);
+    }
+    words
+}
+
+/// Control tokens such as `[_BEG_]` or `[_TT_150]` carry no text.
+fn is_special_token(text: &str) -> bool {
+    text.starts_with("[_") && text.ends_with(']')
+}
+
+/// whisper-server reports full language names ("english"), whisper-cli codes ("en").
+fn normalize_language(language: &str) -> Option<String> {
+    let language = language.trim().to_ascii_lowercase();
+    if language.is_empty() {
+        return None;
+    }
+    crate::whisper::language_code(&language)
+        .map(str::to_string)
+        .or(Some(language))
+}
+
+fn join_segments(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn seconds_to_ms(seconds: f64) -> i64 {
+    (seconds * 1000.0).round() as i64
+}
+
+fn mean(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `whisper-cli -ojf` output for whisper.cpp's `samples/jfk.wav` with ggml-base.en.
+    const CLI_JFK: &str = include_str!("../tests/fixtures/whisper-cli-jfk.json");
+    /// whisper-server `verbose_json` response for the same recording with ggml-base.
+    const SERVER_JFK: &str = include_str!("../tests/fixtures/whisper-server-jfk.json");
+
+    fn word_texts(transcript: &Transcript) -> Vec<&str> {
+        transcript.words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    const JFK_WORDS: [&str; 22] = [
+        "And", "so", "my", "fellow", "Americans,", "ask", "not", "what", "your", "country", "can", "do", "for",
+        "you,", "ask", "what", "you", "can", "do", "for", "your", "country.",
+    ];
+
+    #[test]
+    fn cli_json_segments_and_text() {
+        let transcript = Transcript::from_cli_json(CLI_JFK).unwrap();
+        assert_eq!(
+            transcript.text,
+            "And so my fellow Americans, ask not what your country can do for you, ask what you can do for your country."
+        );
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(transcript.segments.len(), 2);
+        assert_eq!((transcript.segments[0].start_ms, transcript.segments[0].end_ms), (0, 5600));
+        assert_eq!((transcript.segments[1].start_ms, transcript.segments[1].end_ms), (5600, 13700));
+        assert_eq!(transcript.segments[1].text, "what your country can do for you, ask what you can do for your country.");
+    }
+
+    #[test]
+    fn cli_json_merges_tokens_into_words() {
+        let transcript = Transcript::from_cli_json(CLI_JFK).unwrap();
+        assert_eq!(word_texts(&transcript), JFK_WORDS);
+
+        // " Americ" + "ans" + "," spans all three tokens and takes the lowest probability.
+        let americans = &transcript.words[4];
+        assert_eq!((americans.segment, americans.start_ms, americans.end_ms), (0, 1800, 3400));
+        assert!((americans.probability - 0.6874).abs() < 1e-6);
+        assert_eq!(transcript.segment_words(1).count(), 15);
+    }
+
+    #[test]
+    fn cli_json_skips_special_tokens_in_confidence() {
+        let transcript = Transcript::from_cli_json(CLI_JFK).unwrap();
+        // [_BEG_] and the low-probability [_TT_...] tokens must not drag the average down.
+        let expected = [0.9521f32, 0.9932, 0.9970, 0.9981, 0.9845, 0.9997, 0.6874, 0.9874, 0.9958]
+            .iter()
+            .map(|p| p.ln())
+            .sum::<f32>()
+            / 9.0;
+        let first = transcript.segments[0].avg_logprob.unwrap();
+        assert!((first - expected).abs() < 1e-5, "{} != {}", first, expected);
+        let overall = transcript.avg_logprob.unwrap();
+        assert!(overall < 0.0 && overall > -0.1, "{}", overall);
+    }
+
+    #[test]
+    fn cli_json_without_token_offsets_uses_segment_times() {
+        let json = r#"{"result": {"language": "pt"}, "transcription": [{
+            "offsets": {"from": 1000, "to": 2500}, "text": " Olá, mundo",
+            "tokens": [{"text": "[_BEG_]", "p": 0.9}, {"text": " Ol", "p": 0.8}, {"text": "á", "p": 0.9},
+                       {"text": ",", "p": 0.7}, {"text": " mundo", "p": 0.95}]}]}"#;
+        let transcript = Transcript::from_cli_json(json).unwrap();
+        assert_eq!(transcript.language.as_deref(), Some("pt"));
+        assert_eq!(word_texts(&transcript), ["Olá,", "mundo"]);
+        assert!(transcript.words.iter().all(|w| (w.start_ms, w.end_ms) == (1000, 2500)));
+    }
+
+    #[test]
+    fn cli_json_with_no_segments_is_empty() {
+        let transcript = Transcript::from_cli_json(r#"{"result": {"language": "en"}, "transcription": []}"#).unwrap();
+        assert!(transcript.text.is_empty());
+        assert!(transcript.avg_logprob.is_none());
+        assert!(Transcript::from_cli_json("not json").is_err());
+    }
+
+    #[test]
+    fn server_json_segments_and_words() {
+        let transcript = Transcript::from_server_json(SERVER_JFK.as_bytes()).unwrap();
+        assert_eq!(
+            transcript.text,
+            "And so my fellow Americans, ask not what your country can do for you, ask what you can do for your country."
+        );
+        assert_eq!(transcript.segments.len(), 2);
+        assert_eq!((transcript.segments[1].start_ms, transcript.segments[1].end_ms), (5600, 13700));
+        assert_eq!(transcript.segments[0].no_speech_prob, Some(0.0113));
+        assert_eq!(word_texts(&transcript), JFK_WORDS);
+        let americans = &transcript.words[4];
+        assert_eq!((americans.start_ms, americans.end_ms), (1800, 3400));
+        assert!((americans.probability - 0.6874).abs() < 1e-6);
+    }
+
+    #[test]
+    fn server_json_normalizes_language_and_averages_segments() {
+        let transcript = Transcript::from_server_json(SERVER_JFK.as_bytes()).unwrap();
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        let expected = (-0.1706 + -0.1172) / 2.0;
+        assert!((transcript.avg_logprob.unwrap() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn server_json_without_timestamps() {
+        let body = br#"{"task": "transcribe", "language": "portuguese", "duration": 2.0, "text": " Bom dia.",
+            "segments": [{"id": 0, "text": " Bom dia.", "start": 0.0, "end": 2.0, "avg_logprob": -0.3,
+            "no_speech_prob": 0.02, "words": [{"word": " Bom", "probability": 0.9},
+            {"word": " dia", "probability": 0.8}, {"word": ".", "probability": 0.6}]}]}"#;
+        let transcript = Transcript::from_server_json(body).unwrap();
+        assert_eq!(transcript.language.as_deref(), Some("pt"));
+        assert_eq!(word_texts(&transcript), ["Bom", "dia."]);
+        assert_eq!((transcript.words[1].start_ms, transcript.words[1].end_ms), (0, 2000));
+    }
+
+    #[test]
+    fn server_error_is_returned() {
+        let e = Transcript::from_server_json(br#"{"error": "failed to read WAV file"}"#).unwrap_err();
+        assert!(e.to_string().contains("failed to read WAV file"));
+    }
+
+    #[test]
+    fn unknown_language_is_kept_as_is() {
+        assert_eq!(normalize_language(" English "), Some("en".to_string()));
+        assert_eq!(normalize_language("klingon"), Some("klingon".to_string()));
+        assert_eq!(normalize_language(""), None);
+    }
+}
@@ -0,0 +1,474 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::config::{self, Config};
+use crate::transcript::Transcript;
+use crate::{archive, db, delivery, filter, models, postprocess, snippets, tempfiles, whisper, AppState};
+
+/// How to transcribe a saved recording, captured from the config when it starts.
+pub struct TranscriptionSetup {
+    transcriber: Arc<dyn whisper::Transcriber>,
+    model_dir: PathBuf,
+    model_name: String,
+    /// Tried in order if `model_name` fails. Empty when a model was picked explicitly.
+    fallbacks: Vec<String>,
+    job: whisper::TranscribeRequest,
+    filter: config::FilterOptions,
+    postprocess: config::PostProcessOptions,
+    rules: Vec<db::ReplacementRule>,
+    snippets: Vec<db::Snippet>,
+    /// Deadline for the transcriber, scaled to the recording length.
+    timeout: Option<Duration>,
+}
+
+impl TranscriptionSetup {
+    /// Uses the configured model and its fallbacks unless `model_name` is given.
+    pub fn from_config(app_handle: &AppHandle, wav_path: PathBuf, duration_secs: u32, model_name: Option<String>) -> Self {
+        let model_dir = Config::get_models_dir().unwrap_or_else(|_| std::env::temp_dir());
+        let state = app_handle.state::<AppState>();
+        let (model_name, fallbacks, options, filter, postprocess, timeout) = {
+            let cfg = state.config.lock().unwrap();
+            let (model_name, fallbacks) = match model_name {
+                Some(name) => (name, Vec::new()),
+                None => (cfg.model_name.clone(), cfg.model_fallbacks.clone()),
+            };
+            (
+                model_name,
+                fallbacks,
+                cfg.whisper.clone(),
+                cfg.filter.clone(),
+                cfg.postprocess.clone(),
+                cfg.transcription_timeout(duration_secs),
+            )
+        };
+        let (vocabulary, rules, snippets) = {
+            let db = state.db.lock().unwrap();
+            (
+                db.get_vocabulary_for_language(&options.language.trim().to_ascii_lowercase())
+                    .unwrap_or_default(),
+                db.list_rules().unwrap_or_default(),
+                db.list_snippets().unwrap_or_default(),
+            )
+        };
+        let transcriber = state.transcriber.lock().unwrap().clone();
+        Self {
+            transcriber,
+            job: whisper::TranscribeRequest {
+                wav_path,
+                model_path: models::resolve(&model_dir, &model_name),
+                options,
+                prompt: whisper::build_initial_prompt(&vocabulary),
+            },
+            model_dir,
+            model_name,
+            fallbacks,
+            filter,
+            postprocess,
+            rules,
+            snippets,
+            timeout,
+        }
+    }
+
+    pub fn model_used(&self) -> String {
+        self.job.model_path.to_string_lossy().to_string()
+    }
+
+    pub fn whisper_options(&self) -> Option<String> {
+        serde_json::to_string(&self.job.options).ok()
+    }
+
+    /// The session row for a run of this setup, before its outcome is known.
+    pub fn session_record(&self, started_at: i64, duration_secs: u32, truncated: bool) -> db::SessionRecord {
+        db::SessionRecord {
+            model_used: self.model_used(),
+            whisper_options: self.whisper_options(),
+            ..db::SessionRecord::new(started_at, duration_secs, truncated)
+        }
+    }
+
+    /// Transcribes with the model, moving on to each fallback if it fails or
+    /// times out. The error is the first model's if every one fails.
+    pub async fn run(&self) -> Result<TranscriptionOutput> {
+        let filter = filter::TranscriptFilter::new(&self.filter)?;
+        let postprocessor = postprocess::PostProcessor::new(&self.postprocess, &self.rules)?;
+        let mut first_error = None;
+        for name in std::iter::once(&self.model_name).chain(self.fallbacks.iter().filter(|f| **f != self.model_name)) {
+            let job = whisper::TranscribeRequest {
+                model_path: models::resolve(&self.model_dir, name),
+                ..self.job.clone()
+            };
+            match self.transcribe_with(name, &job).await {
+                Ok(raw) => {
+                    let filtered = filter.apply(&raw);
+                    let text = postprocessor.apply(&filtered.text, &self.output_language(&filtered));
+                    return Ok(TranscriptionOutput {
+                        text: self.expand_snippets(text).await?,
+                        filtered,
+                        raw,
+                        model_used: job.model_path.to_string_lossy().to_string(),
+                        fallback: (*name != self.model_name).then(|| name.clone()),
+                    });
+                }
+                Err(e) => {
+                    eprintln!("Transcription with model '{}' failed: {:#}", name, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.expect("at least one model is tried"))
+    }
+
+    /// Runs last so snippet text is inserted exactly as stored. Reading the
+    /// clipboard for `{clipboard}` blocks, so this runs off the async runtime.
+    async fn expand_snippets(&self, text: String) -> Result<String> {
+        if self.snippets.is_empty() {
+            return Ok(text);
+        }
+        let expander = snippets::SnippetExpander::new(&self.postprocess.snippet_keyword, &self.snippets)?;
+        Ok(tokio::task::spawn_blocking(move || expander.expand(&text, snippets::default_variables())).await?)
+    }
+
+    /// Language of the text whisper returned: English when translating, else
+    /// the detected language, the configured one, or English if neither is known.
+    fn output_language(&self, transcript: &Transcript) -> String {
+        if self.job.options.translate {
+            return "en".to_string();
+        }
+        if let Some(language) = &transcript.language {
+            return language.clone();
+        }
+        match self.job.options.language.trim().to_ascii_lowercase().as_str() {
+            "" | "auto" => "en".to_string(),
+            language => language.to_string(),
+        }
+    }
+
+    async fn transcribe_with(&self, model_name: &str, job: &whisper::TranscribeRequest) -> Result<Transcript> {
+        let multilingual = if self.transcriber.capabilities().uses_model {
+            Some(models::read_info(&job.model_path)?.multilingual)
+        } else {
+            None
+        };
+        whisper::validate_options(&job.options, model_name, multilingual)?;
+        whisper::transcribe_with_timeout(self.transcriber.as_ref(), job, self.timeout).await
+    }
+}
+
+/// A successful [`TranscriptionSetup::run`].
+pub struct TranscriptionOutput {
+    /// As whisper produced it.
+    pub raw: Transcript,
+    pub filtered: Transcript,
+    /// `filtered`'s text after post-processing; empty if there was no speech.
+    pub text: String,
+    /// Path of the model that produced the text.
+    pub model_used: String,
+    /// Set when the configured model failed and this fallback was used instead.
+    pub fallback: Option<String>,
+}
+
+impl TranscriptionOutput {
+    /// Fills in `session` with this output. When no speech remained, the raw
+    /// text is stored so the user can see what the filter dropped.
+    pub fn into_record(self, session: db::SessionRecord) -> db::SessionRecord {
+        let no_speech = self.text.is_empty();
+        let raw_text = self.raw.text.clone();
+        let (transcription, transcript) = if no_speech {
+            (self.raw.text.clone(), self.raw)
+        } else {
+            (self.text, self.filtered)
+        };
+        db::SessionRecord {
+            word_count: if no_speech { 0 } else { transcription.split_whitespace().count() as u32 },
+            model_used: self.model_used,
+            fallback_model: self.fallback,
+            transcription,
+            raw_transcription: Some(raw_text),
+            no_speech,
+            transcript: Some(transcript),
+            ..session
+        }
+    }
+}
+
+/// Fills in `session` for a transcription that failed with `e`.
+pub fn failure_record(e: &anyhow::Error, session: db::SessionRecord) -> db::SessionRecord {
+    let (kind, _) = describe_failure(e);
+    db::SessionRecord {
+        error: Some(db::SessionError { kind, message: e.to_string() }),
+        ..session
+    }
+}
+
+/// Everything a background transcription needs once the recording is saved.
+pub struct TranscriptionRequest {
+    pub setup: TranscriptionSetup,
+    /// Owns the WAV file so it is deleted once the request is finished or cancelled.
+    pub recording: tempfiles::TempRecording,
+    pub append: bool,
+    pub started_at: i64,
+    pub duration_secs: u32,
+    pub truncated: bool,
+    /// Fires when the user cancels.
+    pub cancelled: tokio::sync::oneshot::Receiver<()>,
+}
+
+/// A transcription running in the background, kept so it can be cancelled.
+pub struct TranscriptionJob {
+    pub task: tokio::task::JoinHandle<()>,
+    /// Taken when the job is cancelled.
+    pub cancel: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+pub async fn transcribe_and_deliver(app_handle: AppHandle, mut request: TranscriptionRequest) {
+    let start_time = Instant::now();
+    let mut record = request.setup.session_record(request.started_at, request.duration_secs, request.truncated);
+
+    // Dropping the `run` future kills the whisper-cli child. Nothing after it
+    // is cancellable, so the clipboard, the archive and the session row agree.
+    let result = tokio::select! {
+        result = request.setup.run() => result,
+        Ok(()) = &mut request.cancelled => {
+            log_session(&app_handle, db::SessionRecord { cancelled: true, ..record });
+            let _ = app_handle.emit("badge-state", "idle");
+            println!("Transcription cancelled");
+            return;
+        }
+    };
+    record.latency_ms = start_time.elapsed().as_millis() as u32;
+
+    match result {
+        Ok(output) => {
+            if keep_audio(&app_handle) {
+                record.audio_path = archive_audio(request.recording.path()).await;
+            }
+            let text = output.text.clone();
+            let fallback = output.fallback.clone();
+            let record = output.into_record(record);
+            let has_archive = record.audio_path.is_some();
+
+            if record.no_speech {
+                println!("No speech detected (whisper returned {:?})", record.transcription);
+                let _ = app_handle.emit("badge-state", "idle");
+                let _ = tokio::task::spawn_blocking(|| {
+                    delivery::notify("VoiceClip", "No speech detected", false)
+                }).await;
+                log_session(&app_handle, record);
+            } else {
+                println!("Transcription output: {}", text);
+                {
+                    let text_clone = text.clone();
+                    let append = request.append;
+                    if let Err(e) = tokio::task::spawn_blocking(move || {
+                        delivery::copy_to_clipboard(&text_clone, append)
+                    }).await.unwrap_or_else(|e| Err(anyhow::anyhow!(e))) {
+                        eprintln!("Delivery failed: {:?}", e);
+                    }
+                }
+                {
+                    let title = match &fallback {
+                        Some(model) => format!("VoiceClip Success (fallback model {})", model),
+                        None => "VoiceClip Success".to_string(),
+                    };
+                    let _ = tokio::task::spawn_blocking(move || {
+                        delivery::notify(&title, &text, false)
+                    }).await;
+                }
+                log_session(&app_handle, record);
+                let _ = app_handle.emit("badge-state", "success");
+                schedule_idle(&app_handle);
+            }
+
+            if has_archive {
+                let state = app_handle.state::<AppState>();
+                if let Ok(db) = state.db.lock() {
+                    enforce_audio_retention(&app_handle, &db);
+                };
+            }
+        }
+        Err(e) => {
+            eprintln!("Transcription failed: {:?}", e);
+            let (_, message) = describe_failure(&e);
+            let mut record = failure_record(&e, record);
+            record.failed_audio_path = match Config::get_failed_dir().and_then(|dir| request.recording.persist(&dir)) {
+                Ok(path) => Some(path.to_string_lossy().to_string()),
+                Err(e) => {
+                    eprintln!("Failed to keep recording: {:?}", e);
+                    None
+                }
+            };
+            let _ = app_handle.emit("badge-state", "error");
+            let _ = tokio::task::spawn_blocking(move || {
+                delivery::notify("VoiceClip Error", &message, true)
+            }).await;
+            log_session(&app_handle, record);
+            schedule_idle(&app_handle);
+        }
+    }
+}
+
+/// Categorizes a transcription error and picks the message shown to the user.
+fn describe_failure(e: &anyhow::Error) -> (db::ErrorKind, String) {
+    match e.downcast_ref::<whisper::TimeoutError>() {
+        Some(timeout) => (
+            db::ErrorKind::Timeout,
+            format!("Transcription timed out after {}s", timeout.limit.as_secs()),
+        ),
+        None => (db::ErrorKind::Failed, "Failed to transcribe audio".to_string()),
+    }
+}
+
+pub fn log_session(app_handle: &AppHandle, session: db::SessionRecord) {
+    let state = app_handle.state::<AppState>();
+    let _ = state.db.lock().map(|db| db.log_session(session));
+}
+
+pub fn keep_audio(app_handle: &AppHandle) -> bool {
+    let state = app_handle.state::<AppState>();
+    let keep = state.config.lock().map(|cfg| cfg.keep_audio).unwrap_or(false);
+    keep
+}
+
+/// Compresses a recording into the archive without blocking the runtime.
+pub async fn archive_audio(wav_path: &Path) -> Option<String> {
+    let wav_path = wav_path.to_path_buf();
+    match tokio::task::spawn_blocking(move || archive::archive_recording(&wav_path)).await {
+        Ok(Ok(path)) => Some(path.to_string_lossy().to_string()),
+        Ok(Err(e)) => {
+            eprintln!("Failed to archive recording: {:?}", e);
+            None
+        }
+        Err(e) => {
+            eprintln!("Failed to archive recording: {:?}", e);
+            None
+        }
+    }
+}
+
+pub fn enforce_audio_retention(app_handle: &AppHandle, db: &db::Database) {
+    let state = app_handle.state::<AppState>();
+    let Ok((days, max_mb)) = state.config.lock().map(|cfg| (cfg.audio_retention_days, cfg.audio_retention_max_mb)) else {
+        return;
+    };
+    match archive::enforce_retention(db, days, max_mb) {
+        Ok(0) => {}
+        Ok(removed) => println!("Removed {} archived recordings past the retention limit", removed),
+        Err(e) => eprintln!("Failed to apply audio retention: {:?}", e),
+    }
+}
+
+/// Returns the badge to idle after a success or error state has been shown.
+pub fn schedule_idle(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+        let _ = handle.emit("badge-state", "idle");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A silent 16 kHz WAV of `secs` seconds in the system temp directory.
+    fn silent_wav(name: &str, secs: f32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("voiceclip-test-{}-{}.wav", std::process::id(), name));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: crate::audio::WHISPER_SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..(crate::audio::WHISPER_SAMPLE_RATE as f32 * secs) as usize {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    fn mock_setup(wav_path: PathBuf) -> TranscriptionSetup {
+        TranscriptionSetup {
+            transcriber: Arc::new(whisper::MockTranscriber),
+            model_dir: std::env::temp_dir(),
+            model_name: "mock".to_string(),
+            fallbacks: Vec::new(),
+            job: whisper::TranscribeRequest {
+                wav_path,
+                model_path: PathBuf::from("mock"),
+                options: config::WhisperOptions::default(),
+                prompt: None,
+            },
+            filter: config::FilterOptions::default(),
+            postprocess: config::PostProcessOptions::default(),
+            rules: Vec::new(),
+            snippets: Vec::new(),
+            timeout: Some(Duration::from_secs(10)),
+        }
+    }
+
+    fn rule(pattern: &str, replacement: &str) -> db::ReplacementRule {
+        db::ReplacementRule {
+            id: 0,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            is_regex: false,
+            case_sensitive: false,
+            whole_word: true,
+            enabled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_filter_rules_post_processing_and_snippets() {
+        let wav = silent_wav("pipeline", 2.5);
+        let mut setup = mock_setup(wav.clone());
+        setup.filter.patterns.push(r"\s*of audio".to_string());
+        setup.rules.push(rule("mock transcription", "um, dictated text, snippet sign off"));
+        setup.postprocess.steps.push(config::PostProcessStep::RemoveTrailingPeriod);
+        setup.snippets.push(db::Snippet { id: 0, trigger: "sign off".to_string(), body: "Best, Sam".to_string() });
+
+        let output = setup.run().await;
+        let _ = std::fs::remove_file(&wav);
+        let output = output.unwrap();
+
+        assert_eq!(output.raw.text, "Mock transcription of 2.5 seconds of audio.");
+        assert_eq!(output.filtered.text, "Mock transcription of 2.5 seconds.");
+        assert_eq!(output.text, "Dictated text, Best, Sam of 2.5 seconds");
+        assert_eq!(output.fallback, None);
+
+        let record = output.into_record(setup.session_record(100, 3, false));
+        assert_eq!(record.transcription, "Dictated text, Best, Sam of 2.5 seconds");
+        assert_eq!(record.raw_transcription.as_deref(), Some("Mock transcription of 2.5 seconds of audio."));
+        assert_eq!(record.word_count, 7);
+        assert!(!record.no_speech);
+        assert_eq!(record.started_at, 100);
+    }
+
+    #[tokio::test]
+    async fn empty_filter_result_is_recorded_as_no_speech() {
+        let wav = silent_wav("no-speech", 1.0);
+        let mut setup = mock_setup(wav.clone());
+        setup.filter.patterns.push(r"(?i)mock transcription.*".to_string());
+
+        let output = setup.run().await;
+        let _ = std::fs::remove_file(&wav);
+        let record = output.unwrap().into_record(setup.session_record(100, 1, false));
+
+        assert!(record.no_speech);
+        assert_eq!(record.word_count, 0);
+        assert_eq!(record.transcription, "Mock transcription of 1.0 seconds of audio.");
+    }
+
+    #[tokio::test]
+    async fn missing_recording_fails() {
+        let setup = mock_setup(std::env::temp_dir().join("voiceclip-test-missing.wav"));
+        let e = setup.run().await.err().expect("a missing WAV cannot be transcribed");
+        let record = failure_record(&e, setup.session_record(100, 1, false));
+        assert_eq!(record.error.map(|e| e.kind), Some(db::ErrorKind::Failed));
+        assert_eq!(record.model_used, "mock");
+    }
+}
@@ -1,30 +1,323 @@
-use std::path::Path;
-use tokio::process::Command;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
 use anyhow::{Context, Result};
+use serde::Serialize;
+use crate::config::{Config, TranscriberBackend, WhisperOptions};
+use crate::transcript::Transcript;
 
-/// Resolve whisper-cli path: check next to our executable first, then fall back to PATH.
-fn get_whisper_cli_path() -> String {
+/// How long to wait for whisper-server to load its model and start listening.
+const SERVER_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const SERVER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Upper bound for the initial prompt. whisper keeps at most half of its
+/// 448-token text context for the prompt; stay well under that.
+const PROMPT_TOKEN_BUDGET: usize = 200;
+
+/// Language codes accepted by whisper.cpp's `-l` flag, with the names
+/// whisper-server reports for them.
+const WHISPER_LANGUAGES: &[(&str, &str)] = &[
+    ("en", "english"), ("zh", "chinese"), ("de", "german"), ("es", "spanish"), ("ru", "russian"),
+    ("ko", "korean"), ("fr", "french"), ("ja", "japanese"), ("pt", "portuguese"), ("tr", "turkish"),
+    ("pl", "polish"), ("ca", "catalan"), ("nl", "dutch"), ("ar", "arabic"), ("sv", "swedish"),
+    ("it", "italian"), ("id", "indonesian"), ("hi", "hindi"), ("fi", "finnish"),
+    ("vi", "vietnamese"), ("he", "hebrew"), ("uk", "ukrainian"), ("el", "greek"), ("ms", "malay"),
+    ("cs", "czech"), ("ro", "romanian"), ("da", "danish"), ("hu", "hungarian"), ("ta", "tamil"),
+    ("no", "norwegian"), ("th", "thai"), ("ur", "urdu"), ("hr", "croatian"), ("bg", "bulgarian"),
+    ("lt", "lithuanian"), ("la", "latin"), ("mi", "maori"), ("ml", "malayalam"), ("cy", "welsh"),
+    ("sk", "slovak"), ("te", "telugu"), ("fa", "persian"), ("lv", "latvian"), ("bn", "bengali"),
+    ("sr", "serbian"), ("az", "azerbaijani"), ("sl", "slovenian"), ("kn", "kannada"),
+    ("et", "estonian"), ("mk", "macedonian"), ("br", "breton"), ("eu", "basque"),
+    ("is", "icelandic"), ("hy", "armenian"), ("ne", "nepali"), ("mn", "mongolian"),
+    ("bs", "bosnian"), ("kk", "kazakh"), ("sq", "albanian"), ("sw", "swahili"), ("gl", "galician"),
+    ("mr", "marathi"), ("pa", "punjabi"), ("si", "sinhala"), ("km", "khmer"), ("sn", "shona"),
+    ("yo", "yoruba"), ("so", "somali"), ("af", "afrikaans"), ("oc", "occitan"), ("ka", "georgian"),
+    ("be", "belarusian"), ("tg", "tajik"), ("sd", "sindhi"), ("gu", "gujarati"), ("am", "amharic"),
+    ("yi", "yiddish"), ("lo", "lao"), ("uz", "uzbek"), ("fo", "faroese"), ("ht", "haitian creole"),
+    ("ps", "pashto"), ("tk", "turkmen"), ("nn", "nynorsk"), ("mt", "maltese"), ("sa", "sanskrit"),
+    ("lb", "luxembourgish"), ("my", "myanmar"), ("bo", "tibetan"), ("tl", "tagalog"),
+    ("mg", "malagasy"), ("as", "assamese"), ("tt", "tatar"), ("haw", "hawaiian"), ("ln", "lingala"),
+    ("ha", "hausa"), ("ba", "bashkir"), ("jw", "javanese"), ("su", "sundanese"),
+    ("yue", "cantonese"),
+];
+
+/// Maps a whisper language code or full name to its code.
+pub fn language_code(language: &str) -> Option<&'static str> {
+    WHISPER_LANGUAGES
+        .iter()
+        .find(|(code, name)| *code == language || *name == language)
+        .map(|(code, _)| *code)
+}
+
+/// Future returned by [`Transcriber::transcribe`].
+pub type TranscribeFuture<'a> = Pin<Box<dyn Future<Output = Result<Transcript>> + Send + 'a>>;
+
+/// A transcription ran past the deadline given to [`transcribe_with_timeout`].
+#[derive(Debug)]
+pub struct TimeoutError {
+    pub limit: Duration,
+}
+
+impl std::fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Transcription did not finish within {}s", self.limit.as_secs())
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// What a backend supports, so callers can tell engines apart without knowing their type.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    pub name: &'static str,
+    /// Loads the ggml model at `model_path`.
+    pub uses_model: bool,
+    /// Keeps the model loaded between transcriptions.
+    pub persistent: bool,
+    pub language_detection: bool,
+    pub translate: bool,
+    pub initial_prompt: bool,
+    pub timestamps: bool,
+}
+
+/// One transcription job handed to a backend.
+#[derive(Debug, Clone)]
+pub struct TranscribeRequest {
+    pub wav_path: PathBuf,
+    pub model_path: PathBuf,
+    pub options: WhisperOptions,
+    /// Initial prompt priming whisper with vocabulary, see [`build_initial_prompt`].
+    pub prompt: Option<String>,
+}
+
+/// Rough token count for whisper's BPE tokenizer: one token per word plus one
+/// for every further four characters, which over-estimates for plain English
+/// and stays safe for names and jargon.
+fn estimate_tokens(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|word| 1 + word.chars().count().saturating_sub(1) / 4)
+        .sum()
+}
+
+/// Joins vocabulary terms into an initial prompt, dropping terms once the
+/// token budget is used up. Earlier terms take priority.
+pub fn build_initial_prompt(terms: &[String]) -> Option<String> {
+    let mut prompt = String::new();
+    let mut tokens = 0;
+    for term in terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        // The ", " separator costs a token of its own.
+        let cost = estimate_tokens(term) + usize::from(!prompt.is_empty());
+        if tokens + cost > PROMPT_TOKEN_BUDGET {
+            break;
+        }
+        if !prompt.is_empty() {
+            prompt.push_str(", ");
+        }
+        prompt.push_str(term);
+        tokens += cost;
+    }
+    (!prompt.is_empty()).then(|| format!("{}.", prompt))
+}
+
+/// Rejects options whisper would refuse or silently ignore, before any process is started.
+/// `multilingual` comes from the model's header ([`crate::models::ModelInfo`]); when the
+/// model can't be read, the `.en` in whisper.cpp's download names is used instead.
+pub fn validate_options(options: &WhisperOptions, model_name: &str, multilingual: Option<bool>) -> Result<()> {
+    let language = options.language.trim().to_ascii_lowercase();
+    let english_only = match multilingual {
+        Some(multilingual) => !multilingual,
+        None => {
+            let stem = model_name.trim_end_matches(".bin");
+            stem.ends_with(".en") || stem.contains(".en-")
+        }
+    };
+
+    if language != "auto" && !WHISPER_LANGUAGES.iter().any(|(code, _)| *code == language) {
+        anyhow::bail!("Unknown language '{}'. Use an ISO 639-1 code such as 'en' or 'pt', or 'auto'.", options.language);
+    }
+    if english_only && language != "auto" && language != "en" {
+        anyhow::bail!("Model '{}' is English-only and cannot transcribe '{}'.", model_name, language);
+    }
+    if english_only && options.translate {
+        anyhow::bail!("Model '{}' is English-only and cannot translate.", model_name);
+    }
+    if let Some(threads) = options.threads {
+        if !(1..=64).contains(&threads) {
+            anyhow::bail!("threads must be between 1 and 64, got {}", threads);
+        }
+    }
+    if let Some(beam_size) = options.beam_size {
+        if !(1..=16).contains(&beam_size) {
+            anyhow::bail!("beam_size must be between 1 and 16, got {}", beam_size);
+        }
+    }
+    if let Some(best_of) = options.best_of {
+        if !(1..=16).contains(&best_of) {
+            anyhow::bail!("best_of must be between 1 and 16, got {}", best_of);
+        }
+    }
+    if let Some(temperature) = options.temperature {
+        if !(0.0..=1.0).contains(&temperature) {
+            anyhow::bail!("temperature must be between 0.0 and 1.0, got {}", temperature);
+        }
+    }
+    Ok(())
+}
+
+/// whisper-cli flags for the per-request options.
+fn cli_option_args(options: &WhisperOptions) -> Vec<String> {
+    let mut args = vec!["-l".to_string(), options.language.trim().to_ascii_lowercase()];
+    if options.translate {
+        args.push("-tr".to_string());
+    }
+    if let Some(threads) = options.threads {
+        args.extend(["-t".to_string(), threads.to_string()]);
+    }
+    if let Some(beam_size) = options.beam_size {
+        args.extend(["-bs".to_string(), beam_size.to_string()]);
+    }
+    if let Some(best_of) = options.best_of {
+        args.extend(["-bo".to_string(), best_of.to_string()]);
+    }
+    if let Some(temperature) = options.temperature {
+        args.extend(["-tp".to_string(), temperature.to_string()]);
+    }
+    args
+}
+
+fn cli_prompt_args(prompt: Option<&str>) -> Vec<String> {
+    match prompt {
+        Some(prompt) => vec!["--prompt".to_string(), prompt.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// whisper-server `/inference` form fields for the per-request options.
+/// Threads are fixed when the server starts, so they are not included.
+fn server_form_fields(request: &TranscribeRequest) -> Vec<(&'static str, String)> {
+    let options = &request.options;
+    let mut fields = vec![
+        ("language", options.language.trim().to_ascii_lowercase()),
+        ("translate", options.translate.to_string()),
+    ];
+    if let Some(prompt) = &request.prompt {
+        fields.push(("prompt", prompt.clone()));
+    }
+    if let Some(beam_size) = options.beam_size {
+        fields.push(("beam_size", beam_size.to_string()));
+    }
+    if let Some(best_of) = options.best_of {
+        fields.push(("best_of", best_of.to_string()));
+    }
+    if let Some(temperature) = options.temperature {
+        fields.push(("temperature", temperature.to_string()));
+    }
+    fields
+}
+
+pub trait Transcriber: Send + Sync {
+    fn capabilities(&self) -> Capabilities;
+    fn transcribe<'a>(&'a self, request: &'a TranscribeRequest) -> TranscribeFuture<'a>;
+
+    /// Like [`Transcriber::transcribe`], but fails with a [`TimeoutError`] after
+    /// `limit`. Dropping the future kills whisper-cli; backends with a helper
+    /// process override this to stop it, and to leave its startup out of `limit`.
+    fn transcribe_within<'a>(&'a self, request: &'a TranscribeRequest, limit: Duration) -> TranscribeFuture<'a> {
+        Box::pin(with_limit(Some(limit), self.transcribe(request)))
+    }
+
+    /// Stops any helper processes the backend owns. Called on app exit.
+    fn shutdown(&self) {}
+}
+
+/// Runs `request` on `transcriber`, giving up after `limit`.
+pub async fn transcribe_with_timeout(
+    transcriber: &dyn Transcriber,
+    request: &TranscribeRequest,
+    limit: Option<Duration>,
+) -> Result<Transcript> {
+    match limit {
+        Some(limit) => transcriber.transcribe_within(request, limit).await,
+        None => transcriber.transcribe(request).await,
+    }
+}
+
+async fn with_limit<T>(limit: Option<Duration>, future: impl Future<Output = Result<T>>) -> Result<T> {
+    let Some(limit) = limit else {
+        return future.await;
+    };
+    tokio::time::timeout(limit, future).await.unwrap_or_else(|_| Err(TimeoutError { limit }.into()))
+}
+
+pub fn create_transcriber(config: &Config) -> Arc<dyn Transcriber> {
+    match config.transcriber {
+        TranscriberBackend::WhisperCli => Arc::new(WhisperCli),
+        TranscriberBackend::WhisperServer => Arc::new(WhisperServer::new(config.whisper_server_port)),
+        TranscriberBackend::Mock => Arc::new(MockTranscriber),
+    }
+}
+
+/// Resolve a whisper.cpp binary: check next to our executable first, then fall back to PATH.
+fn get_bundled_binary_path(name: &str) -> String {
+    let file_name = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
     if let Ok(exe) = std::env::current_exe() {
         if let Some(dir) = exe.parent() {
-            let bundled = dir.join(if cfg!(windows) { "whisper-cli.exe" } else { "whisper-cli" });
+            let bundled = dir.join(&file_name);
             if bundled.exists() {
                 return bundled.to_string_lossy().to_string();
             }
         }
     }
-    "whisper-cli".to_string()
+    name.to_string()
+}
+
+/// Spawns whisper.cpp's `whisper-cli` once per transcription.
+pub struct WhisperCli;
+
+impl Transcriber for WhisperCli {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            name: "whisper-cli",
+            uses_model: true,
+            persistent: false,
+            language_detection: true,
+            translate: true,
+            initial_prompt: true,
+            timestamps: true,
+        }
+    }
+
+    fn transcribe<'a>(&'a self, request: &'a TranscribeRequest) -> TranscribeFuture<'a> {
+        Box::pin(transcribe_cli(request))
+    }
 }
 
-pub async fn transcribe(wav_path: &Path, model_path: &Path) -> Result<String> {
-    let output = Command::new(get_whisper_cli_path())
+async fn transcribe_cli(request: &TranscribeRequest) -> Result<Transcript> {
+    let wav_path = &request.wav_path;
+    let json_path = wav_path.with_extension("wav.json");
+
+    // Never parse output left over from an earlier run.
+    match tokio::fs::remove_file(&json_path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("Failed to remove stale output {:?}", json_path));
+        }
+        _ => {}
+    }
+
+    let output = Command::new(get_bundled_binary_path("whisper-cli"))
         .arg("-m")
-        .arg(model_path)
+        .arg(&request.model_path)
         .arg("-f")
         .arg(wav_path)
-        .arg("--output-txt")
-        .arg("--no-timestamps")
-        .arg("-l")
-        .arg("auto")
+        .arg("--output-json-full")
+        .args(cli_option_args(&request.options))
+        .args(cli_prompt_args(request.prompt.as_deref()))
+        .kill_on_drop(true)
         .output()
         .await
         .context("Failed to execute whisper-cli. Is it installed and in PATH?")?;
@@ -42,13 +335,510 @@ pub async fn transcribe(wav_path: &Path, model_path: &Path) -> Result<String> {
         anyhow::bail!("{}", msg);
     }
 
-    let txt_path = wav_path.with_extension("wav.txt");
+    // Tokens can split multi-byte characters, so the file is not always valid UTF-8.
+    let parsed = tokio::fs::read(&json_path)
+        .await
+        .context("Failed to read whisper-cli JSON output")
+        .and_then(|bytes| Transcript::from_cli_json(&String::from_utf8_lossy(&bytes)));
+    let _ = tokio::fs::remove_file(&json_path).await;
+
+    match parsed {
+        Ok(transcript) => Ok(transcript),
+        Err(e) => {
+            eprintln!("Falling back to whisper-cli stdout: {:?}", e);
+            Ok(Transcript::from_text(&strip_timestamps(&String::from_utf8_lossy(&output.stdout))))
+        }
+    }
+}
+
+/// Removes the `[00:00:00.000 --> 00:00:02.000]` prefixes whisper-cli prints before each segment.
+fn strip_timestamps(stdout: &str) -> String {
+    stdout
+        .lines()
+        .map(|line| match line.trim_start().strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((stamp, text)) if stamp.contains("-->") => text.trim(),
+            _ => line.trim(),
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Keeps a whisper.cpp `whisper-server` running on a loopback port so the
+/// model stays loaded between recordings. The server is (re)started lazily
+/// when it is not running, has crashed, or a different model is requested.
+/// If it cannot be reached, the recording is transcribed with whisper-cli.
+pub struct WhisperServer {
+    /// 0 picks a free port each time the server starts.
+    port: u16,
+    server: Mutex<Option<RunningServer>>,
+    /// Serialises requests so only one caller restarts the server at a time.
+    gate: tokio::sync::Mutex<()>,
+}
+
+struct RunningServer {
+    child: Child,
+    port: u16,
+    model_path: PathBuf,
+    threads: Option<u32>,
+}
+
+impl WhisperServer {
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            server: Mutex::new(None),
+            gate: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// `limit` only starts once the server is listening: loading a large model
+    /// can take longer than a short clip is allowed, on every cold start.
+    async fn transcribe_with_fallback(&self, request: &TranscribeRequest, limit: Option<Duration>) -> Result<Transcript> {
+        let _gate = self.gate.lock().await;
+        let result = match self.ready_port(request).await {
+            Ok(port) => with_limit(limit, post_inference(port, &request.wav_path, &server_form_fields(request))).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(transcript) => Ok(transcript),
+            Err(e) if e.is::<TimeoutError>() => {
+                self.stop_server();
+                Err(e)
+            }
+            Err(e) => {
+                eprintln!("whisper-server failed, falling back to whisper-cli: {:?}", e);
+                self.stop_server();
+                with_limit(limit, transcribe_cli(request)).await
+            }
+        }
+    }
 
-    let text = tokio::fs::read_to_string(&txt_path)
+    /// The port of a server for `request`'s model, starting one if needed.
+    async fn ready_port(&self, request: &TranscribeRequest) -> Result<u16> {
+        let threads = request.options.threads;
+        match self.serving_port(&request.model_path, threads) {
+            Some(port) => Ok(port),
+            None => self.start_server(&request.model_path, threads).await,
+        }
+    }
+
+    /// The port of the server if it is alive and was started with `model_path` and `threads`.
+    fn serving_port(&self, model_path: &Path, threads: Option<u32>) -> Option<u16> {
+        let mut server = self.server.lock().unwrap();
+        let s = server.as_mut()?;
+        let alive = matches!(s.child.try_wait(), Ok(None));
+        (alive && s.model_path == model_path && s.threads == threads).then_some(s.port)
+    }
+
+    /// Returns the port the new server listens on.
+    async fn start_server(&self, model_path: &Path, threads: Option<u32>) -> Result<u16> {
+        self.stop_server();
+
+        let port = match self.port {
+            0 => free_loopback_port()?,
+            port => port,
+        };
+        // Anything already listening there is not ours, and recordings must not be sent to it.
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            anyhow::bail!("Port {} is already in use by another process", port);
+        }
+
+        let mut command = Command::new(get_bundled_binary_path("whisper-server"));
+        command
+            .arg("-m")
+            .arg(model_path)
+            .arg("--host")
+            .arg("127.0.0.1")
+            .arg("--port")
+            .arg(port.to_string());
+        if let Some(threads) = threads {
+            command.arg("-t").arg(threads.to_string());
+        }
+        let child = command
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to execute whisper-server. Is it installed and in PATH?")?;
+        println!("Started whisper-server on port {} with {:?}", port, model_path);
+
+        *self.server.lock().unwrap() = Some(RunningServer {
+            child,
+            port,
+            model_path: model_path.to_path_buf(),
+            threads,
+        });
+
+        // The server loads the model before it starts listening.
+        let deadline = tokio::time::Instant::now() + SERVER_STARTUP_TIMEOUT;
+        loop {
+            let listening = TcpStream::connect(("127.0.0.1", port)).await.is_ok();
+            // Checked after connecting: if another process took the port in
+            // the meantime, our server fails to bind and exits.
+            if let Some(status) = self.exit_status() {
+                anyhow::bail!("whisper-server exited during startup with {}", status);
+            }
+            if listening {
+                return Ok(port);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                anyhow::bail!("whisper-server did not start listening within {:?}", SERVER_STARTUP_TIMEOUT);
+            }
+            tokio::time::sleep(SERVER_POLL_INTERVAL).await;
+        }
+    }
+
+    fn exit_status(&self) -> Option<std::process::ExitStatus> {
+        let mut server = self.server.lock().unwrap();
+        server.as_mut().and_then(|s| s.child.try_wait().ok().flatten())
+    }
+
+    fn stop_server(&self) {
+        if let Some(mut s) = self.server.lock().unwrap().take() {
+            let _ = s.child.start_kill();
+        }
+    }
+}
+
+impl Transcriber for WhisperServer {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            name: "whisper-server",
+            uses_model: true,
+            persistent: true,
+            language_detection: true,
+            translate: true,
+            initial_prompt: true,
+            timestamps: true,
+        }
+    }
+
+    fn transcribe<'a>(&'a self, request: &'a TranscribeRequest) -> TranscribeFuture<'a> {
+        Box::pin(self.transcribe_with_fallback(request, None))
+    }
+
+    fn transcribe_within<'a>(&'a self, request: &'a TranscribeRequest, limit: Duration) -> TranscribeFuture<'a> {
+        Box::pin(self.transcribe_with_fallback(request, Some(limit)))
+    }
+
+    fn shutdown(&self) {
+        self.stop_server();
+    }
+}
+
+/// Asks the OS for an unused loopback port. It is released again before
+/// whisper-server binds it, which is why `start_server` re-checks.
+fn free_loopback_port() -> Result<u16> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).context("Failed to find a free port")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// POSTs a WAV file to whisper-server's `/inference` endpoint and parses the transcript.
+async fn post_inference(port: u16, wav_path: &Path, fields: &[(&str, String)]) -> Result<Transcript> {
+    const BOUNDARY: &str = "----voiceclip-form-boundary";
+
+    let wav = tokio::fs::read(wav_path)
+        .await
+        .with_context(|| format!("Failed to read {:?}", wav_path))?;
+
+    let mut body = Vec::with_capacity(wav.len() + 512);
+    body.extend_from_slice(format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\n"
+    ).as_bytes());
+    body.extend_from_slice(&wav);
+    body.extend_from_slice(b"\r\n");
+    for (name, value) in std::iter::once(&("response_format", "verbose_json".to_string())).chain(fields) {
+        body.extend_from_slice(format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+        ).as_bytes());
+    }
+    body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+
+    let head = format!(
+        "POST /inference HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nContent-Type: multipart/form-data; boundary={BOUNDARY}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port))
         .await
-        .unwrap_or_else(|_| String::from_utf8_lossy(&output.stdout).to_string());
+        .context("Failed to connect to whisper-server")?;
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.context("Failed to read whisper-server response")?;
+
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .context("Malformed HTTP response from whisper-server")?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let body = &response[header_end + 4..];
+
+    let status_line = head.lines().next().unwrap_or_default();
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .with_context(|| format!("Malformed HTTP status line from whisper-server: {}", status_line))?;
+    let chunked = head.lines().any(|l| {
+        l.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+    let body = if chunked { decode_chunked(body)? } else { body.to_vec() };
+    if status != 200 {
+        anyhow::bail!("whisper-server returned HTTP {}: {}", status, String::from_utf8_lossy(&body));
+    }
+
+    Transcript::from_server_json(&body)
+}
+
+/// Joins the chunks of a `Transfer-Encoding: chunked` body.
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(body.len());
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .context("Malformed chunked response from whisper-server")?;
+        let size_line = String::from_utf8_lossy(&body[..line_end]);
+        // Chunk extensions after ';' are allowed and ignored.
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .with_context(|| format!("Malformed chunk size from whisper-server: {}", size_line))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = body.get(..size).context("Truncated chunked response from whisper-server")?;
+        decoded.extend_from_slice(chunk);
+        body = body.get(size + 2..).unwrap_or_default();
+    }
+}
 
-    let text = text.trim().to_string();
+/// Deterministic backend that never runs a model: reports the length of the
+/// recording instead, so the pipeline can be exercised without whisper.cpp.
+pub struct MockTranscriber;
 
-    Ok(text)
+impl Transcriber for MockTranscriber {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            name: "mock",
+            uses_model: false,
+            persistent: true,
+            language_detection: false,
+            translate: false,
+            initial_prompt: false,
+            timestamps: false,
+        }
+    }
+
+    fn transcribe<'a>(&'a self, request: &'a TranscribeRequest) -> TranscribeFuture<'a> {
+        Box::pin(async move {
+            let reader = hound::WavReader::open(&request.wav_path)
+                .with_context(|| format!("Failed to open {:?}", request.wav_path))?;
+            let spec = reader.spec();
+            let secs = reader.duration() as f32 / spec.sample_rate as f32;
+            Ok(Transcript::from_text(&format!("Mock transcription of {:.1} seconds of audio.", secs)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    const SERVER_JFK: &str = include_str!("../tests/fixtures/whisper-server-jfk.json");
+
+    /// Stands in for whisper-server: accepts one connection, reads the whole
+    /// request and answers with `response`. Yields the port and the request.
+    async fn stand_in(response: Vec<u8>) -> (u16, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let task = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if n == 0 || request_complete(&request) {
+                    break;
+                }
+            }
+            stream.write_all(&response).await.unwrap();
+            stream.shutdown().await.unwrap();
+            request
+        });
+        (port, task)
+    }
+
+    fn request_complete(request: &[u8]) -> bool {
+        let Some(header_end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+            return false;
+        };
+        let head = String::from_utf8_lossy(&request[..header_end]).to_ascii_lowercase();
+        let length: usize = head
+            .lines()
+            .find_map(|l| l.strip_prefix("content-length:"))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0);
+        request.len() >= header_end + 4 + length
+    }
+
+    fn http_response(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!("HTTP/1.1 {}\r\n{}\r\n", status, headers).into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn wav_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("voiceclip-test-{}-{}.wav", std::process::id(), name));
+        std::fs::write(&path, b"RIFF fake wav bytes").unwrap();
+        path
+    }
+
+    async fn post(name: &str, response: Vec<u8>) -> (Result<Transcript>, String) {
+        let wav = wav_file(name);
+        let (port, server) = stand_in(response).await;
+        let fields = [("language", "en".to_string()), ("prompt", "VoiceClip, whisper.".to_string())];
+        let result = post_inference(port, &wav, &fields).await;
+        let _ = std::fs::remove_file(&wav);
+        let request = String::from_utf8_lossy(&server.await.unwrap()).to_string();
+        (result, request)
+    }
+
+    #[tokio::test]
+    async fn posts_multipart_and_parses_verbose_json() {
+        let body = SERVER_JFK.as_bytes();
+        let headers = format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len());
+        let (result, request) = post("ok", http_response("200 OK", &headers, body)).await;
+
+        let transcript = result.unwrap();
+        assert!(transcript.text.starts_with("And so my fellow Americans"));
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(transcript.segments.len(), 2);
+
+        assert!(request.starts_with("POST /inference HTTP/1.1\r\n"));
+        assert!(request.contains("filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF fake wav bytes\r\n"));
+        assert!(request.contains("name=\"response_format\"\r\n\r\nverbose_json\r\n"));
+        assert!(request.contains("name=\"language\"\r\n\r\nen\r\n"));
+        assert!(request.contains("name=\"prompt\"\r\n\r\nVoiceClip, whisper.\r\n"));
+        assert!(request.ends_with("------voiceclip-form-boundary--\r\n"));
+    }
+
+    #[tokio::test]
+    async fn non_200_is_an_error_with_the_body() {
+        let body = br#"{"error": "failed to read audio data"}"#;
+        let headers = format!("Content-Length: {}\r\n", body.len());
+        let (result, _) = post("error", http_response("500 Internal Server Error", &headers, body)).await;
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("HTTP 500"), "{}", message);
+        assert!(message.contains("failed to read audio data"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn decodes_chunked_responses() {
+        let json = SERVER_JFK.as_bytes();
+        let mut body = Vec::new();
+        for chunk in json.chunks(100) {
+            body.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            body.extend_from_slice(chunk);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"0\r\n\r\n");
+        let response = http_response("200 OK", "Transfer-Encoding: chunked\r\n", &body);
+        let (result, _) = post("chunked", response).await;
+        assert_eq!(result.unwrap().words.len(), 22);
+    }
+
+    #[tokio::test]
+    async fn server_error_json_is_an_error() {
+        let body = br#"{"error": "model not loaded"}"#;
+        let headers = format!("Content-Length: {}\r\n", body.len());
+        let (result, _) = post("error-json", http_response("200 OK", &headers, body)).await;
+        assert!(result.unwrap_err().to_string().contains("model not loaded"));
+    }
+
+    #[tokio::test]
+    async fn malformed_response_is_an_error() {
+        let (result, _) = post("malformed", b"garbage".to_vec()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_a_port_another_process_listens_on() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = WhisperServer::new(port);
+        let request = TranscribeRequest {
+            wav_path: wav_file("port"),
+            model_path: PathBuf::from("ggml-base.en.bin"),
+            options: WhisperOptions::default(),
+            prompt: None,
+        };
+        let result = server.ready_port(&request).await;
+        let _ = std::fs::remove_file(&request.wav_path);
+        assert!(result.unwrap_err().to_string().contains("already in use"));
+        assert!(server.server.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn chunked_decoding_ignores_extensions_and_rejects_truncation() {
+        assert_eq!(decode_chunked(b"4;name=value\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n").unwrap(), b"Wikipedia");
+        assert!(decode_chunked(b"a\r\nshort\r\n").is_err());
+        assert!(decode_chunked(b"zz\r\n").is_err());
+    }
+
+    fn options(language: &str, translate: bool) -> WhisperOptions {
+        WhisperOptions { language: language.to_string(), translate, ..WhisperOptions::default() }
+    }
+
+    #[test]
+    fn english_only_comes_from_the_model_header() {
+        let quantized = "ggml-base.en-q5_1.bin";
+        assert!(validate_options(&options("pt", false), quantized, Some(false)).is_err());
+        assert!(validate_options(&options("en", true), quantized, Some(false)).is_err());
+        assert!(validate_options(&options("auto", false), quantized, Some(false)).is_ok());
+        // A renamed multilingual model is not English-only just because of its name.
+        assert!(validate_options(&options("pt", true), "ggml-custom.en.bin", Some(true)).is_ok());
+    }
+
+    #[test]
+    fn english_only_falls_back_to_the_model_name() {
+        assert!(validate_options(&options("pt", false), "ggml-base.en-q5_1.bin", None).is_err());
+        assert!(validate_options(&options("pt", false), "base.en", None).is_err());
+        assert!(validate_options(&options("pt", false), "ggml-base-q5_1.bin", None).is_ok());
+    }
+
+    struct Slow;
+
+    impl Transcriber for Slow {
+        fn capabilities(&self) -> Capabilities {
+            MockTranscriber.capabilities()
+        }
+
+        fn transcribe<'a>(&'a self, _request: &'a TranscribeRequest) -> TranscribeFuture<'a> {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(Transcript::from_text("late"))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn transcription_past_the_limit_is_a_timeout_error() {
+        let request = TranscribeRequest {
+            wav_path: PathBuf::from("unused.wav"),
+            model_path: PathBuf::from("unused.bin"),
+            options: WhisperOptions::default(),
+            prompt: None,
+        };
+        let limit = Duration::from_millis(20);
+        let error = transcribe_with_timeout(&Slow, &request, Some(limit)).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<TimeoutError>().map(|e| e.limit), Some(limit));
+    }
 }
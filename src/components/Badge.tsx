@@ -9,6 +9,7 @@ const LEVEL_BUFFER_SIZE = 30;
 
 export default function Badge() {
   const [state, setState] = useState<BadgeState>("idle");
+  const [remaining, setRemaining] = useState<number | null>(null);
   const [menuOpen, setMenuOpen] = useState(false);
   const [menuPos, setMenuPos] = useState({ x: 0, y: 0 });
   const canvasRef = useRef<HTMLCanvasElement>(null);
@@ -23,6 +24,18 @@ export default function Badge() {
     return () => { unlisten.then((fn) => fn()); };
   }, []);
 
+  // Listen for recording-warning events (seconds left before max_recording_duration)
+  useEffect(() => {
+    if (state !== "recording") {
+      setRemaining(null);
+      return;
+    }
+    const unlisten = listen<number>("recording-warning", (event) => {
+      setRemaining(event.payload);
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, [state]);
+
   // Listen for audio-level events and draw waveform
   useEffect(() => {
     if (state !== "recording") {
@@ -115,7 +128,7 @@ export default function Badge() {
     return () => { unlisten.then((fn) => fn()); };
   }, []);
 
-  const icon = state === "recording" ? "\u23F9"
+  const icon = state === "recording" ? (remaining !== null ? String(remaining) : "\u23F9")
     : state === "processing" ? "\u2026"
     : state === "success" ? "\u2713"
     : state === "error" ? "\u2717"
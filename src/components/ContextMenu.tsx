@@ -1,6 +1,6 @@
 import { useEffect, useRef } from "react";
 import { exit } from "@tauri-apps/plugin-process";
-import { openSettingsWindow, openStatsWindow } from "../lib/commands";
+import { cancelRecording, openSettingsWindow, openStatsWindow } from "../lib/commands";
 
 interface ContextMenuProps {
   x: number;
@@ -37,6 +37,8 @@ export default function ContextMenu({ x, y, onClose }: ContextMenuProps) {
         boxShadow: "0 4px 12px rgba(0,0,0,0.5)",
       }}
     >
+      <MenuItem label="Cancel" onClick={() => { cancelRecording(); onClose(); }} />
+      <div style={{ borderTop: "1px solid #444", margin: "4px 0" }} />
       <MenuItem label="Stats" onClick={() => { openStatsWindow(); onClose(); }} />
       <MenuItem label="Settings" onClick={() => { openSettingsWindow(); onClose(); }} />
       <div style={{ borderTop: "1px solid #444", margin: "4px 0" }} />
@@ -1,14 +1,34 @@
 import { useState, useEffect } from "react";
-import { getConfig, saveConfig, listInputDevices, type Config } from "../lib/commands";
+import { getConfig, saveConfig, listInputDevices, listModels, setModel, listRules, addRule, updateRule, removeRule, previewRule, listSnippets, addSnippet, updateSnippet, removeSnippet, listVocabulary, addVocabularyTerm, removeVocabularyTerm, type Config, type FilterOptions, type HotkeyAction, type HotkeyMode, type ModelInfo, type PostProcessOptions, type PostProcessStep, type SpokenGrammar, type ReplacementRule, type RulePreview, type Snippet, type SaveConfigError, type TranscriberBackend, type VocabularyTerm, type WhisperOptions } from "../lib/commands";
+
+const STEP_LABELS: Record<PostProcessStep, string> = {
+  trim: "Trim surrounding spaces",
+  normalize_whitespace: "Normalize whitespace",
+  remove_fillers: "Remove filler words",
+  capitalize: "Capitalize sentences",
+  add_trailing_period: "Add trailing period",
+  remove_trailing_period: "Remove trailing period",
+  spoken_punctuation: "Spoken punctuation",
+};
+
+const ACTION_LABELS: Record<HotkeyAction, string> = {
+  toggle: "Start / stop recording",
+  cancel: "Cancel recording",
+  recopy_last: "Re-copy last transcription",
+  toggle_append: "Toggle append mode",
+  open_stats: "Open stats",
+};
 
 export default function SettingsWindow() {
   const [config, setConfig] = useState<Config | null>(null);
   const [devices, setDevices] = useState<string[]>([]);
+  const [models, setModels] = useState<ModelInfo[]>([]);
   const [status, setStatus] = useState("");
 
   useEffect(() => {
     getConfig().then(setConfig);
     listInputDevices().then(setDevices);
+    listModels().then(setModels);
   }, []);
 
   if (!config) return <div style={styles.container}><p>Loading...</p></div>;
@@ -18,11 +38,52 @@ export default function SettingsWindow() {
       await saveConfig(config);
       setStatus("Saved!");
       setTimeout(() => setStatus(""), 2000);
+    } catch (e) {
+      setStatus(`Error: ${describeSaveError(e as SaveConfigError)}`);
+    }
+  };
+
+  const handleModelChange = async (name: string) => {
+    try {
+      await setModel(name);
+      setConfig({ ...config, model_name: name });
+      setStatus(`Model set to ${name}`);
+      setTimeout(() => setStatus(""), 2000);
     } catch (e) {
       setStatus(`Error: ${e}`);
     }
   };
 
+  const bindings = Object.entries(config.keybindings);
+
+  const setWhisper = (patch: Partial<WhisperOptions>) => {
+    setConfig({ ...config, whisper: { ...config.whisper, ...patch } });
+  };
+
+  const setFilter = (patch: Partial<FilterOptions>) => {
+    setConfig({ ...config, filter: { ...config.filter, ...patch } });
+  };
+
+  const setPostProcess = (patch: Partial<PostProcessOptions>) => {
+    setConfig({ ...config, postprocess: { ...config.postprocess, ...patch } });
+  };
+
+  const steps = config.postprocess.steps;
+  const moveStep = (from: number, to: number) => {
+    const next = [...steps];
+    next.splice(to, 0, next.splice(from, 1)[0]);
+    setPostProcess({ steps: next });
+  };
+  const unusedSteps = (Object.keys(STEP_LABELS) as PostProcessStep[]).filter((s) => !steps.includes(s));
+
+  const lines = (value: string) => value.split("\n").filter((line) => line.trim() !== "");
+
+  const optionalNumber = (value: string) => (value === "" ? null : Number(value));
+
+  const setBindings = (entries: [string, HotkeyAction][]) => {
+    setConfig({ ...config, keybindings: Object.fromEntries(entries) });
+  };
+
   const handleAlwaysOnTopChange = async (checked: boolean) => {
     setConfig({ ...config, always_on_top: checked });
     // Apply immediately to the badge window by saving config
@@ -52,7 +113,42 @@ export default function SettingsWindow() {
           style={styles.input}
         />
         <p style={styles.help}>Examples: Super+Alt+V, Ctrl+Shift+R, F9. Use "None" to disable.</p>
-        <p style={styles.help}>Changes take effect on restart.</p>
+        <select
+          value={config.hotkey_mode}
+          onChange={(e) => setConfig({ ...config, hotkey_mode: e.target.value as HotkeyMode })}
+          style={{ ...styles.input, marginTop: 8 }}
+        >
+          <option value="toggle">Toggle (press to start, press again to stop)</option>
+          <option value="hold">Hold to record (release to transcribe)</option>
+        </select>
+      </Section>
+
+      <Section label="Extra Keybindings">
+        {bindings.map(([chord, action], i) => (
+          <div key={i} style={{ display: "flex", gap: 6, marginBottom: 6 }}>
+            <input
+              type="text"
+              value={chord}
+              onChange={(e) => setBindings(bindings.map((b, j) => (j === i ? [e.target.value, b[1]] : b)))}
+              style={{ ...styles.input, flex: 1 }}
+            />
+            <select
+              value={action}
+              onChange={(e) => setBindings(bindings.map((b, j) => (j === i ? [b[0], e.target.value as HotkeyAction] : b)))}
+              style={{ ...styles.input, flex: 1 }}
+            >
+              {(Object.keys(ACTION_LABELS) as HotkeyAction[]).map((a) => (
+                <option key={a} value={a}>{ACTION_LABELS[a]}</option>
+              ))}
+            </select>
+            <button onClick={() => setBindings(bindings.filter((_, j) => j !== i))} style={styles.smallButton}>
+              Remove
+            </button>
+          </div>
+        ))}
+        <button onClick={() => setBindings([...bindings, ["", "cancel"]])} style={styles.smallButton}>
+          Add keybinding
+        </button>
       </Section>
 
       <Section label="Microphone">
@@ -68,6 +164,201 @@ export default function SettingsWindow() {
         </select>
       </Section>
 
+      <Section label="Transcription">
+        <label style={styles.label}>Model</label>
+        <select value={config.model_name} onChange={(e) => handleModelChange(e.target.value)} style={styles.input}>
+          {!models.some((m) => m.name === config.model_name) && (
+            <option value={config.model_name}>{config.model_name} (not found)</option>
+          )}
+          {models.map((m) => (
+            <option key={m.name} value={m.name}>
+              {m.name} — {m.model_type}, {m.quantization}, {(m.size_bytes / 1e6).toFixed(0)} MB,{" "}
+              {m.multilingual ? "multilingual" : "English-only"}
+            </option>
+          ))}
+        </select>
+        <p style={styles.help}>Models are read from the models directory. Switching takes effect immediately.</p>
+        <label style={{ ...styles.label, display: "block", marginTop: 10 }}>Fallback models</label>
+        <textarea
+          rows={2}
+          value={config.model_fallbacks.join("\n")}
+          onChange={(e) => setConfig({ ...config, model_fallbacks: lines(e.target.value) })}
+          style={styles.input}
+        />
+        <p style={styles.help}>One per line, tried in order if the model above is missing, fails or times out.</p>
+        <label style={{ ...styles.label, display: "block", marginTop: 10 }}>Backend</label>
+        <select
+          value={config.transcriber}
+          onChange={(e) => setConfig({ ...config, transcriber: e.target.value as TranscriberBackend })}
+          style={styles.input}
+        >
+          <option value="whisper_cli">whisper-cli</option>
+          <option value="whisper_server">whisper-server (keeps model loaded)</option>
+          <option value="mock">Mock (no model, for testing)</option>
+        </select>
+        {config.transcriber === "whisper_server" && (
+          <div style={{ marginTop: 10 }}>
+            <label style={styles.label}>Server Port</label>
+            <input
+              type="number"
+              min={0}
+              max={65535}
+              value={config.whisper_server_port}
+              onChange={(e) => setConfig({ ...config, whisper_server_port: parseInt(e.target.value, 10) || 0 })}
+              style={styles.input}
+            />
+            <p style={styles.help}>
+              whisper-server listens on 127.0.0.1 only. Use 0 to pick a free port automatically. If it fails or
+              the port is taken, whisper-cli is used instead.
+            </p>
+          </div>
+        )}
+        <div style={{ marginTop: 10 }}>
+          <label style={styles.label}>Language</label>
+          <input
+            type="text"
+            value={config.whisper.language}
+            onChange={(e) => setWhisper({ language: e.target.value })}
+            style={styles.input}
+          />
+          <p style={styles.help}>ISO 639-1 code such as en or pt, or "auto" to detect. English-only models (*.en) only accept en or auto.</p>
+        </div>
+        <label style={{ ...styles.checkboxLabel, marginTop: 10 }}>
+          <input
+            type="checkbox"
+            checked={config.whisper.translate}
+            onChange={(e) => setWhisper({ translate: e.target.checked })}
+          />
+          Translate to English
+        </label>
+        <div style={{ display: "flex", gap: 6, marginTop: 10 }}>
+          <NumberField label="Threads" value={config.whisper.threads} onChange={(v) => setWhisper({ threads: optionalNumber(v) })} />
+          <NumberField label="Beam size" value={config.whisper.beam_size} onChange={(v) => setWhisper({ beam_size: optionalNumber(v) })} />
+          <NumberField label="Best of" value={config.whisper.best_of} onChange={(v) => setWhisper({ best_of: optionalNumber(v) })} />
+          <NumberField label="Temperature" value={config.whisper.temperature} step={0.1} onChange={(v) => setWhisper({ temperature: optionalNumber(v) })} />
+        </div>
+        <p style={styles.help}>Leave blank to use whisper's defaults.</p>
+        <div style={{ display: "flex", gap: 6, marginTop: 10 }}>
+          <NumberField
+            label="Timeout (s)"
+            value={config.transcription_timeout_secs}
+            onChange={(v) => setConfig({ ...config, transcription_timeout_secs: Number(v) || 0 })}
+          />
+          <NumberField
+            label="+ s per audio s"
+            value={config.transcription_timeout_factor}
+            step={0.5}
+            onChange={(v) => setConfig({ ...config, transcription_timeout_factor: Number(v) || 0 })}
+          />
+        </div>
+        <p style={styles.help}>whisper is stopped if it runs longer than this. A timeout of 0 disables the limit.</p>
+      </Section>
+
+      <Section label="Noise Filter">
+        <label style={styles.checkboxLabel}>
+          <input
+            type="checkbox"
+            checked={config.filter.enabled}
+            onChange={(e) => setFilter({ enabled: e.target.checked })}
+          />
+          Drop text whisper produces on silence or noise
+        </label>
+        <div style={{ marginTop: 10 }}>
+          <label style={styles.label}>Denylisted phrases (one per line)</label>
+          <textarea
+            rows={4}
+            value={config.filter.denylist.join("\n")}
+            onChange={(e) => setFilter({ denylist: lines(e.target.value) })}
+            style={styles.input}
+          />
+        </div>
+        <div style={{ marginTop: 10 }}>
+          <label style={styles.label}>Patterns to remove (regex, one per line)</label>
+          <textarea
+            rows={3}
+            value={config.filter.patterns.join("\n")}
+            onChange={(e) => setFilter({ patterns: lines(e.target.value) })}
+            style={{ ...styles.input, fontFamily: "monospace" }}
+          />
+        </div>
+        <div style={{ marginTop: 10 }}>
+          <label style={styles.label}>No-Speech Threshold: {config.filter.no_speech_threshold.toFixed(2)}</label>
+          <input
+            type="range"
+            min={0.1}
+            max={1.0}
+            step={0.05}
+            value={config.filter.no_speech_threshold}
+            onChange={(e) => setFilter({ no_speech_threshold: parseFloat(e.target.value) })}
+            style={{ width: "100%" }}
+          />
+          <p style={styles.help}>whisper-server only. 1.00 keeps every segment.</p>
+        </div>
+      </Section>
+
+      <Section label="Replacements">
+        <RulesEditor />
+      </Section>
+
+      <Section label="Post-Processing">
+        {steps.map((step, i) => (
+          <div key={step} style={{ display: "flex", gap: 6, marginBottom: 6, alignItems: "center" }}>
+            <span style={{ ...styles.label, flex: 1 }}>{i + 1}. {STEP_LABELS[step]}</span>
+            <button onClick={() => moveStep(i, i - 1)} disabled={i === 0} style={styles.smallButton}>↑</button>
+            <button onClick={() => moveStep(i, i + 1)} disabled={i === steps.length - 1} style={styles.smallButton}>↓</button>
+            <button onClick={() => setPostProcess({ steps: steps.filter((_, j) => j !== i) })} style={styles.smallButton}>
+              Remove
+            </button>
+          </div>
+        ))}
+        {unusedSteps.length > 0 && (
+          <select
+            value=""
+            onChange={(e) => setPostProcess({ steps: [...steps, e.target.value as PostProcessStep] })}
+            style={styles.input}
+          >
+            <option value="">Add step...</option>
+            {unusedSteps.map((s) => (
+              <option key={s} value={s}>{STEP_LABELS[s]}</option>
+            ))}
+          </select>
+        )}
+        {steps.includes("remove_fillers") && (
+          <div style={{ marginTop: 10 }}>
+            <label style={styles.label}>Filler words (one per line)</label>
+            <textarea
+              rows={3}
+              value={config.postprocess.filler_words.join("\n")}
+              onChange={(e) => setPostProcess({ filler_words: lines(e.target.value) })}
+              style={styles.input}
+            />
+          </div>
+        )}
+        {steps.includes("spoken_punctuation") && (
+          <SpokenGrammarEditor
+            grammars={config.postprocess.spoken_punctuation}
+            onChange={(spoken_punctuation) => setPostProcess({ spoken_punctuation })}
+          />
+        )}
+        <p style={styles.help}>Applied in order to the text before it is copied. Whisper's original text is kept in Stats.</p>
+      </Section>
+
+      <Section label="Snippets">
+        <label style={styles.label}>Keyword</label>
+        <input
+          type="text"
+          value={config.postprocess.snippet_keyword}
+          onChange={(e) => setPostProcess({ snippet_keyword: e.target.value })}
+          style={styles.input}
+        />
+        <p style={styles.help}>Say the keyword and then a trigger, e.g. "{config.postprocess.snippet_keyword || "snippet"} sign off".</p>
+        <SnippetsEditor />
+      </Section>
+
+      <Section label="Vocabulary">
+        <VocabularyEditor />
+      </Section>
+
       <Section label="Window">
         <label style={styles.checkboxLabel}>
           <input
@@ -79,6 +370,41 @@ export default function SettingsWindow() {
         </label>
       </Section>
 
+      <Section label="Auto-Stop">
+        <label style={styles.checkboxLabel}>
+          <input
+            type="checkbox"
+            checked={config.vad_enabled}
+            onChange={(e) => setConfig({ ...config, vad_enabled: e.target.checked })}
+          />
+          Stop recording after silence
+        </label>
+        <div style={{ marginTop: 10 }}>
+          <label style={styles.label}>Silence Threshold: {config.vad_threshold.toFixed(3)}</label>
+          <input
+            type="range"
+            min={0.001}
+            max={0.1}
+            step={0.001}
+            value={config.vad_threshold}
+            onChange={(e) => setConfig({ ...config, vad_threshold: parseFloat(e.target.value) })}
+            style={{ width: "100%" }}
+          />
+        </div>
+        <div style={{ marginTop: 10 }}>
+          <label style={styles.label}>Silence Duration: {(config.vad_silence_ms / 1000).toFixed(1)}s</label>
+          <input
+            type="range"
+            min={500}
+            max={10000}
+            step={500}
+            value={config.vad_silence_ms}
+            onChange={(e) => setConfig({ ...config, vad_silence_ms: parseInt(e.target.value, 10) })}
+            style={{ width: "100%" }}
+          />
+        </div>
+      </Section>
+
       <Section label="General">
         <label style={styles.checkboxLabel}>
           <input
@@ -88,6 +414,28 @@ export default function SettingsWindow() {
           />
           Append mode (add to existing clipboard)
         </label>
+        <label style={{ ...styles.checkboxLabel, marginTop: 10 }}>
+          <input
+            type="checkbox"
+            checked={config.keep_audio}
+            onChange={(e) => setConfig({ ...config, keep_audio: e.target.checked })}
+          />
+          Keep recordings (compressed FLAC, playable from Stats)
+        </label>
+        {config.keep_audio && (
+          <div style={{ display: "flex", gap: 6, marginTop: 10 }}>
+            <NumberField
+              label="Keep for (days)"
+              value={config.audio_retention_days}
+              onChange={(v) => setConfig({ ...config, audio_retention_days: Number(v) || 0 })}
+            />
+            <NumberField
+              label="Max size (MB)"
+              value={config.audio_retention_max_mb}
+              onChange={(v) => setConfig({ ...config, audio_retention_max_mb: Number(v) || 0 })}
+            />
+          </div>
+        )}
         <div style={{ marginTop: 10 }}>
           <label style={styles.label}>Badge Opacity: {config.badge_opacity.toFixed(1)}</label>
           <input
@@ -110,6 +458,423 @@ export default function SettingsWindow() {
   );
 }
 
+// Vocabulary lives in the database, so edits apply immediately rather than on Save.
+function VocabularyEditor() {
+  const [terms, setTerms] = useState<VocabularyTerm[]>([]);
+  const [term, setTerm] = useState("");
+  const [language, setLanguage] = useState("");
+  const [error, setError] = useState("");
+
+  const refresh = () => listVocabulary().then(setTerms).catch((e) => setError(String(e)));
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const handleAdd = async () => {
+    try {
+      await addVocabularyTerm(term, language || null);
+      setTerm("");
+      setError("");
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleRemove = async (id: number) => {
+    try {
+      await removeVocabularyTerm(id);
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <>
+      {terms.map((t) => (
+        <div key={t.id} style={{ display: "flex", gap: 6, marginBottom: 6, alignItems: "center" }}>
+          <span style={{ flex: 1, fontSize: 13 }}>{t.term}</span>
+          <span style={{ ...styles.help, marginTop: 0 }}>{t.language === "*" ? "all languages" : t.language}</span>
+          <button onClick={() => handleRemove(t.id)} style={styles.smallButton}>
+            Remove
+          </button>
+        </div>
+      ))}
+      <div style={{ display: "flex", gap: 6 }}>
+        <input
+          type="text"
+          placeholder="Name, acronym or phrase"
+          value={term}
+          onChange={(e) => setTerm(e.target.value)}
+          style={{ ...styles.input, flex: 3 }}
+        />
+        <input
+          type="text"
+          placeholder="Language"
+          value={language}
+          onChange={(e) => setLanguage(e.target.value)}
+          style={{ ...styles.input, flex: 1 }}
+        />
+        <button onClick={handleAdd} disabled={!term.trim()} style={styles.smallButton}>
+          Add
+        </button>
+      </div>
+      <p style={styles.help}>
+        Terms are passed to whisper as an initial prompt. Leave the language blank to use a term for every language.
+        Earlier terms win when the list exceeds whisper's prompt budget.
+      </p>
+      {error && <p style={{ ...styles.help, color: "#dc143c" }}>{error}</p>}
+    </>
+  );
+}
+
+// Line breaks are shown as \n so they can be typed in a single-line input.
+const showBreaks = (text: string) => text.replace(/\n/g, "\\n");
+const parseBreaks = (text: string) => text.replace(/\\n/g, "\n");
+
+function SpokenGrammarEditor({ grammars, onChange }: {
+  grammars: Record<string, SpokenGrammar>;
+  onChange: (grammars: Record<string, SpokenGrammar>) => void;
+}) {
+  const languages = Object.keys(grammars);
+  const [language, setLanguage] = useState(languages[0] ?? "en");
+  const [newLanguage, setNewLanguage] = useState("");
+  const grammar = grammars[language] ?? { escape: "literal", commands: {} };
+  const commands = Object.entries(grammar.commands);
+
+  const setGrammar = (patch: Partial<SpokenGrammar>) => {
+    onChange({ ...grammars, [language]: { ...grammar, ...patch } });
+  };
+  const setCommands = (entries: [string, string][]) => setGrammar({ commands: Object.fromEntries(entries) });
+
+  const handleAddLanguage = () => {
+    const code = newLanguage.trim().toLowerCase();
+    if (!code) return;
+    if (!grammars[code]) onChange({ ...grammars, [code]: { escape: "literal", commands: {} } });
+    setLanguage(code);
+    setNewLanguage("");
+  };
+
+  return (
+    <div style={{ marginTop: 10 }}>
+      <label style={styles.label}>Spoken commands</label>
+      <div style={{ display: "flex", gap: 6, marginTop: 4 }}>
+        <select value={language} onChange={(e) => setLanguage(e.target.value)} style={{ ...styles.input, flex: 1 }}>
+          {languages.map((l) => (
+            <option key={l} value={l}>{l}</option>
+          ))}
+        </select>
+        <input
+          type="text"
+          placeholder="Language code"
+          value={newLanguage}
+          onChange={(e) => setNewLanguage(e.target.value)}
+          style={{ ...styles.input, flex: 1 }}
+        />
+        <button onClick={handleAddLanguage} disabled={!newLanguage.trim()} style={styles.smallButton}>
+          Add language
+        </button>
+      </div>
+      <div style={{ marginTop: 6 }}>
+        <label style={styles.label}>Escape phrase</label>
+        <input
+          type="text"
+          value={grammar.escape}
+          onChange={(e) => setGrammar({ escape: e.target.value })}
+          style={styles.input}
+        />
+      </div>
+      {commands.map(([phrase, text], i) => (
+        <div key={i} style={{ display: "flex", gap: 6, marginTop: 6 }}>
+          <input
+            type="text"
+            value={phrase}
+            onChange={(e) => setCommands(commands.map((c, j) => (j === i ? [e.target.value, c[1]] : c)))}
+            style={{ ...styles.input, flex: 2 }}
+          />
+          <input
+            type="text"
+            value={showBreaks(text)}
+            onChange={(e) => setCommands(commands.map((c, j) => (j === i ? [c[0], parseBreaks(e.target.value)] : c)))}
+            style={{ ...styles.input, flex: 1, fontFamily: "monospace" }}
+          />
+          <button onClick={() => setCommands(commands.filter((_, j) => j !== i))} style={styles.smallButton}>
+            Remove
+          </button>
+        </div>
+      ))}
+      <button onClick={() => setCommands([...commands, ["", ""]])} style={{ ...styles.smallButton, marginTop: 6 }}>
+        Add command
+      </button>
+      <p style={styles.help}>
+        The grammar matching the transcript's language is used. Spaces in the inserted text are kept: ", " attaches to
+        the previous word, " (" to the next. Say the escape phrase before a command to type its words instead.
+      </p>
+    </div>
+  );
+}
+
+const EMPTY_RULE: ReplacementRule = {
+  id: 0,
+  pattern: "",
+  replacement: "",
+  is_regex: false,
+  case_sensitive: false,
+  whole_word: true,
+  enabled: true,
+};
+
+function RulesEditor() {
+  const [rules, setRules] = useState<ReplacementRule[]>([]);
+  const [draft, setDraft] = useState<ReplacementRule>(EMPTY_RULE);
+  const [preview, setPreview] = useState<RulePreview[] | null>(null);
+  const [error, setError] = useState("");
+
+  const refresh = () => listRules().then(setRules).catch((e) => setError(String(e)));
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const run = async (action: () => Promise<unknown>) => {
+    try {
+      await action();
+      setError("");
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const setDraftField = (patch: Partial<ReplacementRule>) => {
+    setDraft({ ...draft, ...patch });
+    setPreview(null);
+  };
+
+  const handleAdd = () =>
+    run(async () => {
+      await addRule(draft);
+      setDraft(EMPTY_RULE);
+      setPreview(null);
+      refresh();
+    });
+
+  const flags = (r: ReplacementRule) =>
+    [r.is_regex && "regex", r.case_sensitive && "match case", r.whole_word && "whole word"].filter(Boolean).join(", ");
+
+  return (
+    <>
+      {rules.map((r) => (
+        <div key={r.id} style={{ display: "flex", gap: 6, marginBottom: 6, alignItems: "center" }}>
+          <input
+            type="checkbox"
+            checked={r.enabled}
+            onChange={(e) => run(async () => {
+              await updateRule({ ...r, enabled: e.target.checked });
+              refresh();
+            })}
+          />
+          <span style={{ flex: 1, fontSize: 13, fontFamily: r.is_regex ? "monospace" : undefined }}>
+            {r.pattern} → {r.replacement || <em>(remove)</em>}
+          </span>
+          <span style={{ ...styles.help, marginTop: 0 }}>{flags(r)}</span>
+          <button onClick={() => run(async () => {
+            await removeRule(r.id);
+            refresh();
+          })} style={styles.smallButton}>
+            Remove
+          </button>
+        </div>
+      ))}
+      <div style={{ display: "flex", gap: 6 }}>
+        <input
+          type="text"
+          placeholder="Find"
+          value={draft.pattern}
+          onChange={(e) => setDraftField({ pattern: e.target.value })}
+          style={{ ...styles.input, flex: 1, fontFamily: draft.is_regex ? "monospace" : undefined }}
+        />
+        <input
+          type="text"
+          placeholder="Replace with"
+          value={draft.replacement}
+          onChange={(e) => setDraftField({ replacement: e.target.value })}
+          style={{ ...styles.input, flex: 1 }}
+        />
+      </div>
+      <div style={{ display: "flex", gap: 12, marginTop: 6, alignItems: "center" }}>
+        <label style={styles.checkboxLabel}>
+          <input type="checkbox" checked={draft.is_regex} onChange={(e) => setDraftField({ is_regex: e.target.checked })} />
+          Regex
+        </label>
+        <label style={styles.checkboxLabel}>
+          <input type="checkbox" checked={draft.case_sensitive} onChange={(e) => setDraftField({ case_sensitive: e.target.checked })} />
+          Match case
+        </label>
+        <label style={styles.checkboxLabel}>
+          <input type="checkbox" checked={draft.whole_word} onChange={(e) => setDraftField({ whole_word: e.target.checked })} />
+          Whole word
+        </label>
+        <span style={{ flex: 1 }} />
+        <button onClick={() => run(async () => setPreview(await previewRule(draft)))} disabled={!draft.pattern.trim()} style={styles.smallButton}>
+          Test
+        </button>
+        <button onClick={handleAdd} disabled={!draft.pattern.trim()} style={styles.smallButton}>
+          Add
+        </button>
+      </div>
+      {preview && (
+        <div style={{ marginTop: 8 }}>
+          <p style={{ ...styles.help, marginTop: 0 }}>
+            {preview.length === 0 ? "No change to your last 50 sessions." : `Changes ${preview.length} of your last 50 sessions:`}
+          </p>
+          {preview.map((p) => (
+            <div key={p.session_id} style={{ fontSize: 12, marginBottom: 6 }}>
+              <div style={{ color: "#888" }}>{p.before}</div>
+              <div style={{ color: "#ccc" }}>{p.after}</div>
+            </div>
+          ))}
+        </div>
+      )}
+      <p style={styles.help}>
+        Rules run in order on every transcription, before post-processing. Regex replacements can use $1 for capture groups.
+      </p>
+      {error && <p style={{ ...styles.help, color: "#dc143c" }}>{error}</p>}
+    </>
+  );
+}
+
+function SnippetsEditor() {
+  const [snippets, setSnippets] = useState<Snippet[]>([]);
+  const [editingId, setEditingId] = useState<number | null>(null);
+  const [trigger, setTrigger] = useState("");
+  const [body, setBody] = useState("");
+  const [error, setError] = useState("");
+
+  const refresh = () => listSnippets().then(setSnippets).catch((e) => setError(String(e)));
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  const resetForm = () => {
+    setEditingId(null);
+    setTrigger("");
+    setBody("");
+  };
+
+  const handleSave = async () => {
+    try {
+      if (editingId === null) {
+        await addSnippet(trigger, body);
+      } else {
+        await updateSnippet({ id: editingId, trigger, body });
+      }
+      resetForm();
+      setError("");
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleRemove = async (id: number) => {
+    try {
+      await removeSnippet(id);
+      if (id === editingId) resetForm();
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div style={{ marginTop: 10 }}>
+      {snippets.map((s) => (
+        <div key={s.id} style={{ display: "flex", gap: 6, marginBottom: 6, alignItems: "center" }}>
+          <span style={{ fontSize: 13 }}>{s.trigger}</span>
+          <span style={{ ...styles.help, marginTop: 0, flex: 1, overflow: "hidden", textOverflow: "ellipsis", whiteSpace: "nowrap" }}>
+            {s.body.replace(/\n/g, " ⏎ ")}
+          </span>
+          <button
+            onClick={() => {
+              setEditingId(s.id);
+              setTrigger(s.trigger);
+              setBody(s.body);
+            }}
+            style={styles.smallButton}
+          >
+            Edit
+          </button>
+          <button onClick={() => handleRemove(s.id)} style={styles.smallButton}>
+            Remove
+          </button>
+        </div>
+      ))}
+      <input
+        type="text"
+        placeholder="Trigger, e.g. sign off"
+        value={trigger}
+        onChange={(e) => setTrigger(e.target.value)}
+        style={styles.input}
+      />
+      <textarea
+        rows={4}
+        placeholder="Text to insert"
+        value={body}
+        onChange={(e) => setBody(e.target.value)}
+        style={{ ...styles.input, marginTop: 6 }}
+      />
+      <div style={{ display: "flex", gap: 6, marginTop: 6 }}>
+        <button onClick={handleSave} disabled={!trigger.trim()} style={styles.smallButton}>
+          {editingId === null ? "Add snippet" : "Save snippet"}
+        </button>
+        {editingId !== null && (
+          <button onClick={resetForm} style={styles.smallButton}>
+            Cancel
+          </button>
+        )}
+      </div>
+      <p style={styles.help}>{"Use {date}, {time} and {clipboard} in the text to fill them in when the snippet is inserted."}</p>
+      {error && <p style={{ ...styles.help, color: "#dc143c" }}>{error}</p>}
+    </div>
+  );
+}
+
+function describeSaveError(e: SaveConfigError): string {
+  switch (e.kind) {
+    case "invalid_hotkey":
+      return `"${e.hotkey}" is not a valid hotkey`;
+    case "hotkey_registration":
+      return `Could not bind "${e.hotkey}": ${e.message}`;
+    case "duplicate_hotkey":
+      return `"${e.hotkey}" is already used by another binding`;
+    default:
+      return e.message ?? String(e);
+  }
+}
+
+function NumberField({ label, value, step, onChange }: {
+  label: string;
+  value: number | null;
+  step?: number;
+  onChange: (value: string) => void;
+}) {
+  return (
+    <div style={{ flex: 1 }}>
+      <label style={styles.label}>{label}</label>
+      <input
+        type="number"
+        step={step ?? 1}
+        value={value ?? ""}
+        onChange={(e) => onChange(e.target.value)}
+        style={styles.input}
+      />
+    </div>
+  );
+}
+
 function Section({ label, children }: { label: string; children: React.ReactNode }) {
   return (
     <div style={styles.section}>
@@ -172,6 +937,15 @@ const styles: Record<string, React.CSSProperties> = {
     color: "#eee",
     cursor: "pointer",
   },
+  smallButton: {
+    padding: "6px 12px",
+    fontSize: 13,
+    background: "#2a2a2a",
+    color: "#eee",
+    border: "1px solid #444",
+    borderRadius: 4,
+    cursor: "pointer",
+  },
   button: {
     padding: "8px 24px",
     fontSize: 14,
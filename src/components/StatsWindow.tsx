@@ -1,5 +1,20 @@
 import { useState, useEffect } from "react";
-import { getStatsSummary, getRecentSessions, type StatsSummary, type SessionRow } from "../lib/commands";
+import {
+  getStatsSummary,
+  getRecentSessions,
+  getSessionSegments,
+  getSessionAudio,
+  retrySession,
+  retranscribe,
+  getTranscripts,
+  type StatsSummary,
+  type SessionRow,
+  type SegmentRow,
+  type TranscriptRow,
+} from "../lib/commands";
+
+// Words whisper was less sure about than this are highlighted.
+const LOW_CONFIDENCE = 0.5;
 
 export default function StatsWindow() {
   const [summary, setSummary] = useState<StatsSummary | null>(null);
@@ -26,7 +41,11 @@ export default function StatsWindow() {
       <div style={styles.sessionList}>
         {sessions.length === 0 && <p style={styles.empty}>No sessions yet.</p>}
         {sessions.map((s) => (
-          <SessionItem key={s.id} session={s} />
+          <SessionItem
+            key={s.id}
+            session={s}
+            onUpdate={(updated) => setSessions((prev) => prev.map((p) => (p.id === updated.id ? updated : p)))}
+          />
         ))}
       </div>
     </div>
@@ -42,7 +61,8 @@ function SummaryCard({ label, value }: { label: string; value: string }) {
   );
 }
 
-function SessionItem({ session }: { session: SessionRow }) {
+function SessionItem({ session, onUpdate }: { session: SessionRow; onUpdate: (session: SessionRow) => void }) {
+  const [segments, setSegments] = useState<SegmentRow[] | null>(null);
   const isError = session.error !== null;
   const date = new Date(session.started_at * 1000);
   const dateStr = date.toLocaleString();
@@ -52,21 +72,256 @@ function SessionItem({ session }: { session: SessionRow }) {
       ? session.transcription.slice(0, 80) + "..."
       : session.transcription;
 
+  const toggleSegments = () => {
+    if (segments) {
+      setSegments(null);
+    } else {
+      getSessionSegments(session.id).then(setSegments);
+    }
+  };
+
   return (
-    <div style={{ ...styles.session, borderLeftColor: isError ? "#dc143c" : "#444" }}>
+    <div
+      style={{ ...styles.session, borderLeftColor: isError ? "#dc143c" : "#444", cursor: isError ? "default" : "pointer" }}
+      title={session.whisper_options ?? undefined}
+      onClick={isError ? undefined : toggleSegments}
+    >
       <div style={styles.sessionHeader}>
         <span>{dateStr}</span>
         <span>{session.duration_secs}s</span>
         <span>{session.word_count} words</span>
         <span>{session.latency_ms}ms</span>
+        {session.truncated && <span style={{ color: "#e6a23c" }}>truncated</span>}
+        {session.cancelled && <span>cancelled</span>}
+        {session.no_speech && <span>no speech</span>}
+        {session.error_kind === "timeout" && <span style={{ color: "#dc143c" }}>timeout</span>}
+        {session.language && <span>{session.language}</span>}
+        {session.fallback_model && (
+          <span style={{ color: "#e6a23c" }} title={session.model_used}>fallback: {session.fallback_model}</span>
+        )}
       </div>
-      <div style={{ ...styles.sessionBody, color: isError ? "#dc143c" : "#ccc" }}>
-        {preview}
+      {segments ? (
+        <>
+          <Segments segments={segments} fallback={session.transcription} />
+          {session.raw_transcription !== null && session.raw_transcription !== session.transcription && (
+            <div style={{ ...styles.sessionBody, marginTop: 6 }}>
+              <span style={styles.timestamp}>Copied:</span> {session.transcription}
+            </div>
+          )}
+        </>
+      ) : (
+        <div style={{ ...styles.sessionBody, color: isError ? "#dc143c" : "#ccc" }}>
+          {preview}
+        </div>
+      )}
+      {(session.audio_path || session.failed_audio_path) && (
+        <AudioPlayer sessionId={session.id} mimeType={session.audio_path ? "audio/flac" : "audio/wav"} />
+      )}
+      {session.failed_audio_path && <RetryControls sessionId={session.id} onUpdate={onUpdate} />}
+      {segments && !isError && (session.audio_path || session.failed_audio_path) && <Alternates session={session} />}
+    </div>
+  );
+}
+
+function AudioPlayer({ sessionId, mimeType }: { sessionId: number; mimeType: string }) {
+  const [url, setUrl] = useState<string | null>(null);
+  const [error, setError] = useState("");
+
+  useEffect(() => () => {
+    if (url) URL.revokeObjectURL(url);
+  }, [url]);
+
+  const load = async (e: React.MouseEvent) => {
+    e.stopPropagation();
+    try {
+      const bytes = await getSessionAudio(sessionId);
+      setUrl(URL.createObjectURL(new Blob([bytes], { type: mimeType })));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  if (url) {
+    return <audio controls autoPlay src={url} onClick={(e) => e.stopPropagation()} style={{ width: "100%", marginTop: 6 }} />;
+  }
+  return (
+    <div style={{ display: "flex", gap: 6, marginTop: 6, alignItems: "center" }}>
+      <button onClick={load} style={styles.button}>Play</button>
+      {error && <span style={{ color: "#dc143c", fontSize: 12 }}>{error}</span>}
+    </div>
+  );
+}
+
+function RetryControls({ sessionId, onUpdate }: { sessionId: number; onUpdate: (session: SessionRow) => void }) {
+  const [model, setModel] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState("");
+
+  const handleRetry = async () => {
+    setBusy(true);
+    setError("");
+    try {
+      onUpdate(await retrySession(sessionId, model.trim() || null));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div style={{ display: "flex", gap: 6, marginTop: 6, alignItems: "center" }}>
+      <input
+        type="text"
+        placeholder="Model (default: current)"
+        value={model}
+        onChange={(e) => setModel(e.target.value)}
+        style={styles.input}
+      />
+      <button onClick={handleRetry} disabled={busy} style={styles.button}>
+        {busy ? "Retrying..." : "Retry"}
+      </button>
+      {error && <span style={{ color: "#dc143c", fontSize: 12 }}>{error}</span>}
+    </div>
+  );
+}
+
+function Alternates({ session }: { session: SessionRow }) {
+  const [transcripts, setTranscripts] = useState<TranscriptRow[]>([]);
+  const [model, setModel] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    getTranscripts(session.id).then(setTranscripts);
+  }, [session.id]);
+
+  const handleRetranscribe = async () => {
+    setBusy(true);
+    setError("");
+    try {
+      const row = await retranscribe(session.id, model.trim());
+      setTranscripts((prev) => [...prev, row]);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div onClick={(e) => e.stopPropagation()} style={{ cursor: "default" }}>
+      {transcripts.map((t) => (
+        <div key={t.id} style={{ marginTop: 8 }}>
+          <div style={styles.sessionHeader}>
+            <span>{t.model_used}</span>
+            <span>{t.latency_ms}ms</span>
+            {t.language && <span>{t.language}</span>}
+          </div>
+          <TranscriptDiff original={session.transcription} alternate={t.transcription} />
+        </div>
+      ))}
+      <div style={{ display: "flex", gap: 6, marginTop: 6, alignItems: "center" }}>
+        <input
+          type="text"
+          placeholder="Model to compare"
+          value={model}
+          onChange={(e) => setModel(e.target.value)}
+          style={styles.input}
+        />
+        <button onClick={handleRetranscribe} disabled={busy || !model.trim()} style={styles.button}>
+          {busy ? "Transcribing..." : "Re-transcribe"}
+        </button>
+        {error && <span style={{ color: "#dc143c", fontSize: 12 }}>{error}</span>}
       </div>
     </div>
   );
 }
 
+// Original on the left, alternate on the right; words only in one side are highlighted.
+function TranscriptDiff({ original, alternate }: { original: string; alternate: string }) {
+  const ops = diffWords(original.split(/\s+/).filter(Boolean), alternate.split(/\s+/).filter(Boolean));
+  const side = (keep: "removed" | "added", style: React.CSSProperties) =>
+    ops
+      .filter((op) => op.kind === "same" || op.kind === keep)
+      .map((op, i) => (
+        <span key={i} style={op.kind === "same" ? undefined : style}>
+          {keep === "removed" ? op.left : op.right}{" "}
+        </span>
+      ));
+
+  return (
+    <div style={{ display: "flex", gap: 12 }}>
+      <div style={{ ...styles.sessionBody, flex: 1 }}>{side("removed", styles.removed)}</div>
+      <div style={{ ...styles.sessionBody, flex: 1 }}>
+        {alternate ? side("added", styles.added) : <span style={{ color: "#666" }}>(no speech)</span>}
+      </div>
+    </div>
+  );
+}
+
+type DiffOp = { kind: "same" | "removed" | "added"; left?: string; right?: string };
+
+// Word-level diff via longest common subsequence, ignoring case and punctuation.
+function diffWords(a: string[], b: string[]): DiffOp[] {
+  const key = (w: string) => w.toLowerCase().replace(/[^\p{L}\p{N}]/gu, "");
+  const lcs: number[][] = Array.from({ length: a.length + 1 }, () => new Array(b.length + 1).fill(0));
+  for (let i = a.length - 1; i >= 0; i--) {
+    for (let j = b.length - 1; j >= 0; j--) {
+      lcs[i][j] = key(a[i]) === key(b[j]) ? lcs[i + 1][j + 1] + 1 : Math.max(lcs[i + 1][j], lcs[i][j + 1]);
+    }
+  }
+
+  const ops: DiffOp[] = [];
+  let i = 0;
+  let j = 0;
+  while (i < a.length && j < b.length) {
+    if (key(a[i]) === key(b[j])) {
+      ops.push({ kind: "same", left: a[i++], right: b[j++] });
+    } else if (lcs[i + 1][j] >= lcs[i][j + 1]) {
+      ops.push({ kind: "removed", left: a[i++] });
+    } else {
+      ops.push({ kind: "added", right: b[j++] });
+    }
+  }
+  while (i < a.length) ops.push({ kind: "removed", left: a[i++] });
+  while (j < b.length) ops.push({ kind: "added", right: b[j++] });
+  return ops;
+}
+
+function Segments({ segments, fallback }: { segments: SegmentRow[]; fallback: string }) {
+  if (segments.length === 0) {
+    return <div style={styles.sessionBody}>{fallback}</div>;
+  }
+  return (
+    <>
+      {segments.map((seg, i) => (
+        <div key={i} style={{ display: "flex", gap: 8, marginTop: 4 }}>
+          <span style={styles.timestamp}>{formatMs(seg.start_ms)}–{formatMs(seg.end_ms)}</span>
+          <span style={styles.sessionBody}>
+            {seg.words.length === 0
+              ? seg.text
+              : seg.words.map((w, j) => (
+                  <span
+                    key={j}
+                    title={`${Math.round(w.probability * 100)}%`}
+                    style={w.probability < LOW_CONFIDENCE ? styles.lowConfidence : undefined}
+                  >
+                    {w.text}{" "}
+                  </span>
+                ))}
+          </span>
+        </div>
+      ))}
+    </>
+  );
+}
+
+function formatMs(ms: number): string {
+  const secs = ms / 1000;
+  return `${Math.floor(secs / 60)}:${(secs % 60).toFixed(1).padStart(4, "0")}`;
+}
+
 const styles: Record<string, React.CSSProperties> = {
   container: {
     padding: 20,
@@ -136,6 +391,42 @@ const styles: Record<string, React.CSSProperties> = {
     lineHeight: 1.4,
     color: "#ccc",
   },
+  timestamp: {
+    fontSize: 11,
+    color: "#777",
+    fontVariantNumeric: "tabular-nums",
+    whiteSpace: "nowrap" as const,
+    paddingTop: 2,
+  },
+  input: {
+    padding: "4px 8px",
+    fontSize: 12,
+    background: "#333",
+    border: "1px solid #555",
+    borderRadius: 4,
+    color: "#eee",
+  },
+  button: {
+    padding: "4px 12px",
+    fontSize: 12,
+    background: "#444",
+    border: "none",
+    borderRadius: 4,
+    color: "#eee",
+    cursor: "pointer",
+  },
+  lowConfidence: {
+    color: "#e6a23c",
+    textDecoration: "underline dotted",
+  },
+  removed: {
+    color: "#f08080",
+    background: "rgba(220, 20, 60, 0.15)",
+  },
+  added: {
+    color: "#90ee90",
+    background: "rgba(60, 179, 113, 0.15)",
+  },
   empty: {
     color: "#666",
     fontSize: 14,
@@ -1,15 +1,74 @@
 import { invoke } from "@tauri-apps/api/core";
 
+export type HotkeyMode = "toggle" | "hold";
+
+export type HotkeyAction = "toggle" | "cancel" | "recopy_last" | "toggle_append" | "open_stats";
+
+export type TranscriberBackend = "whisper_cli" | "whisper_server" | "mock";
+
+export interface WhisperOptions {
+  language: string;
+  translate: boolean;
+  threads: number | null;
+  beam_size: number | null;
+  best_of: number | null;
+  temperature: number | null;
+}
+
+export type PostProcessStep =
+  | "trim"
+  | "normalize_whitespace"
+  | "remove_fillers"
+  | "capitalize"
+  | "add_trailing_period"
+  | "remove_trailing_period"
+  | "spoken_punctuation";
+
+export interface SpokenGrammar {
+  escape: string;
+  commands: Record<string, string>;
+}
+
+export interface PostProcessOptions {
+  steps: PostProcessStep[];
+  filler_words: string[];
+  spoken_punctuation: Record<string, SpokenGrammar>;
+  snippet_keyword: string;
+}
+
+export interface FilterOptions {
+  enabled: boolean;
+  denylist: string[];
+  patterns: string[];
+  no_speech_threshold: number;
+}
+
 export interface Config {
+  transcriber: TranscriberBackend;
+  whisper_server_port: number;
   model_name: string;
+  model_fallbacks: string[];
+  whisper: WhisperOptions;
+  filter: FilterOptions;
+  postprocess: PostProcessOptions;
   hotkey: string;
+  hotkey_mode: HotkeyMode;
   badge_opacity: number;
   max_recording_duration: number;
+  transcription_timeout_secs: number;
+  transcription_timeout_factor: number;
   append_mode: boolean;
+  keep_audio: boolean;
+  audio_retention_days: number;
+  audio_retention_max_mb: number;
   microphone: string | null;
   always_on_top: boolean;
   badge_x: number | null;
   badge_y: number | null;
+  vad_enabled: boolean;
+  vad_threshold: number;
+  vad_silence_ms: number;
+  keybindings: Record<string, HotkeyAction>;
 }
 
 export interface StatsSummary {
@@ -25,15 +84,106 @@ export interface SessionRow {
   word_count: number;
   transcription: string;
   latency_ms: number;
+  truncated: boolean;
+  cancelled: boolean;
+  no_speech: boolean;
+  whisper_options: string | null;
+  language: string | null;
+  avg_logprob: number | null;
   error: string | null;
+  error_kind: "failed" | "timeout" | null;
+  failed_audio_path: string | null;
+  audio_path: string | null;
+  model_used: string;
+  fallback_model: string | null;
+  raw_transcription: string | null;
+}
+
+export interface Word {
+  segment: number;
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  probability: number;
+}
+
+export interface SegmentRow {
+  start_ms: number;
+  end_ms: number;
+  text: string;
+  avg_logprob: number | null;
+  no_speech_prob: number | null;
+  words: Word[];
+}
+
+export interface ModelInfo {
+  name: string;
+  size_bytes: number;
+  model_type: string;
+  quantization: string;
+  multilingual: boolean;
+}
+
+export interface TranscriptRow {
+  id: number;
+  session_id: number;
+  created_at: number;
+  model_used: string;
+  transcription: string;
+  latency_ms: number;
+  whisper_options: string | null;
+  language: string | null;
+  avg_logprob: number | null;
+}
+
+export interface ReplacementRule {
+  id: number;
+  pattern: string;
+  replacement: string;
+  is_regex: boolean;
+  case_sensitive: boolean;
+  whole_word: boolean;
+  enabled: boolean;
+}
+
+export interface RulePreview {
+  session_id: number;
+  started_at: number;
+  before: string;
+  after: string;
 }
 
+export interface Snippet {
+  id: number;
+  trigger: string;
+  body: string;
+}
+
+export interface VocabularyTerm {
+  id: number;
+  term: string;
+  language: string;
+}
+
+export type SaveConfigError =
+  | { kind: "invalid_hotkey"; hotkey: string; message: string }
+  | { kind: "hotkey_registration"; hotkey: string; message: string }
+  | { kind: "duplicate_hotkey"; hotkey: string; message: string }
+  | { kind: "invalid_whisper_options"; message: string }
+  | { kind: "invalid_filter"; message: string }
+  | { kind: "invalid_timeout"; message: string }
+  | { kind: "save"; message: string };
+
 export type BadgeState = "idle" | "recording" | "processing" | "success" | "error";
 
 export function toggleRecording(): Promise<void> {
   return invoke("toggle_recording");
 }
 
+export function cancelRecording(): Promise<void> {
+  return invoke("cancel_recording");
+}
+
 export function getConfig(): Promise<Config> {
   return invoke("get_config");
 }
@@ -46,6 +196,14 @@ export function listInputDevices(): Promise<string[]> {
   return invoke("list_input_devices");
 }
 
+export function listModels(): Promise<ModelInfo[]> {
+  return invoke("list_models");
+}
+
+export function setModel(name: string): Promise<ModelInfo> {
+  return invoke("set_model", { name });
+}
+
 export function getStatsSummary(): Promise<StatsSummary> {
   return invoke("get_stats_summary");
 }
@@ -54,6 +212,74 @@ export function getRecentSessions(limit: number): Promise<SessionRow[]> {
   return invoke("get_recent_sessions", { limit });
 }
 
+export function getSessionSegments(sessionId: number): Promise<SegmentRow[]> {
+  return invoke("get_session_segments", { sessionId });
+}
+
+export function retrySession(sessionId: number, model: string | null): Promise<SessionRow> {
+  return invoke("retry_session", { sessionId, model });
+}
+
+export function getSessionAudio(sessionId: number): Promise<ArrayBuffer> {
+  return invoke("get_session_audio", { sessionId });
+}
+
+export function retranscribe(sessionId: number, model: string): Promise<TranscriptRow> {
+  return invoke("retranscribe", { sessionId, model });
+}
+
+export function getTranscripts(sessionId: number): Promise<TranscriptRow[]> {
+  return invoke("get_transcripts", { sessionId });
+}
+
+export function listRules(): Promise<ReplacementRule[]> {
+  return invoke("list_rules");
+}
+
+export function addRule(rule: ReplacementRule): Promise<ReplacementRule> {
+  return invoke("add_rule", { rule });
+}
+
+export function updateRule(rule: ReplacementRule): Promise<void> {
+  return invoke("update_rule", { rule });
+}
+
+export function removeRule(id: number): Promise<void> {
+  return invoke("remove_rule", { id });
+}
+
+export function previewRule(rule: ReplacementRule): Promise<RulePreview[]> {
+  return invoke("preview_rule", { rule });
+}
+
+export function listSnippets(): Promise<Snippet[]> {
+  return invoke("list_snippets");
+}
+
+export function addSnippet(trigger: string, body: string): Promise<Snippet> {
+  return invoke("add_snippet", { trigger, body });
+}
+
+export function updateSnippet(snippet: Snippet): Promise<void> {
+  return invoke("update_snippet", { snippet });
+}
+
+export function removeSnippet(id: number): Promise<void> {
+  return invoke("remove_snippet", { id });
+}
+
+export function listVocabulary(): Promise<VocabularyTerm[]> {
+  return invoke("list_vocabulary");
+}
+
+export function addVocabularyTerm(term: string, language: string | null): Promise<number> {
+  return invoke("add_vocabulary_term", { term, language });
+}
+
+export function removeVocabularyTerm(id: number): Promise<void> {
+  return invoke("remove_vocabulary_term", { id });
+}
+
 export function openSettingsWindow(): Promise<void> {
   return invoke("open_settings_window");
 }